@@ -0,0 +1,166 @@
+// 无参考的一致性分析：没有 GT 时比较所有方法两两之间的 IOU 与 Dice，并按分歧程度对图片排序，优先标注分歧最大的图片
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// 以 methods 的顺序排列的对称矩阵，对角线为 1；无法读取的配对为 None，不参与平均
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgreementMatrix {
+    pub iou: Vec<Vec<Option<f64>>>,
+    pub dice: Vec<Vec<Option<f64>>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageAgreement {
+    pub filename: String,
+    pub group: String,
+    pub matrix: AgreementMatrix,
+    // 1 - 可读取配对的 Dice 平均值，越大表示方法之间分歧越大；没有可比较的配对时为 None
+    pub disagreement: Option<f64>,
+    pub paths: HashMap<String, String>,
+    // 无法读取的文件，相关的配对跳过
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgreementReport {
+    pub methods: Vec<String>,
+    // 按分歧从大到小排序，只包含至少有一对方法可以比较的图片
+    pub images: Vec<ImageAgreement>,
+    // 每对方法在可读取该配对的图片上的平均值
+    pub overall: AgreementMatrix,
+    // 每对方法参与平均的图片数
+    pub pair_counts: Vec<Vec<usize>>,
+    // 没有任何可比较配对的图片，不参与排序
+    pub skipped: Vec<ImageAgreement>,
+}
+
+impl AgreementMatrix {
+    pub fn identity(size: usize) -> AgreementMatrix {
+        let identity: Vec<Vec<Option<f64>>> = (0..size)
+            .map(|i| (0..size).map(|j| (i == j).then_some(1.0)).collect())
+            .collect();
+        AgreementMatrix {
+            iou: identity.clone(),
+            dice: identity,
+        }
+    }
+
+    pub fn set(&mut self, i: usize, j: usize, iou: f64, dice: f64) {
+        self.iou[i][j] = Some(iou);
+        self.iou[j][i] = Some(iou);
+        self.dice[i][j] = Some(dice);
+        self.dice[j][i] = Some(dice);
+    }
+
+    fn pairs(size: usize) -> impl Iterator<Item = (usize, usize)> {
+        (0..size).flat_map(move |i| (i + 1..size).map(move |j| (i, j)))
+    }
+
+    // 可读取配对的 Dice 平均值；只有一个方法时为 1，没有可读取的配对时为 None
+    fn mean_pairwise_dice(&self) -> Option<f64> {
+        let size = self.dice.len();
+        if size < 2 {
+            return Some(1.0);
+        }
+        let values: Vec<f64> = Self::pairs(size).filter_map(|(i, j)| self.dice[i][j]).collect();
+        if values.is_empty() {
+            return None;
+        }
+        Some(values.iter().sum::<f64>() / values.len() as f64)
+    }
+}
+
+impl ImageAgreement {
+    pub fn new(
+        filename: String,
+        group: String,
+        matrix: AgreementMatrix,
+        paths: HashMap<String, String>,
+        errors: Vec<String>,
+    ) -> Self {
+        ImageAgreement {
+            disagreement: matrix.mean_pairwise_dice().map(|dice| 1.0 - dice),
+            filename,
+            group,
+            matrix,
+            paths,
+            errors,
+        }
+    }
+}
+
+// 汇总所有图片：没有可比较配对的图片单独列出，其余按配对计算整体平均矩阵，并按分歧从大到小排序
+pub fn build_report(methods: Vec<String>, images: Vec<ImageAgreement>) -> AgreementReport {
+    let (mut images, skipped): (Vec<_>, Vec<_>) = images.into_iter().partition(|image| image.disagreement.is_some());
+
+    let size = methods.len();
+    let mut overall = AgreementMatrix::identity(size);
+    let mut pair_counts = vec![vec![0; size]; size];
+    for (i, j) in AgreementMatrix::pairs(size) {
+        let pairs: Vec<(f64, f64)> = images
+            .iter()
+            .filter_map(|image| Some((image.matrix.iou[i][j]?, image.matrix.dice[i][j]?)))
+            .collect();
+        pair_counts[i][j] = pairs.len();
+        pair_counts[j][i] = pairs.len();
+        if pairs.is_empty() {
+            continue;
+        }
+        let count = pairs.len() as f64;
+        let iou = pairs.iter().map(|(iou, _)| iou).sum::<f64>() / count;
+        let dice = pairs.iter().map(|(_, dice)| dice).sum::<f64>() / count;
+        overall.set(i, j, iou, dice);
+    }
+
+    images.sort_by(|a, b| b.disagreement.unwrap_or(0.0).total_cmp(&a.disagreement.unwrap_or(0.0)));
+    AgreementReport {
+        methods,
+        images,
+        overall,
+        pair_counts,
+        skipped,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(filename: &str, matrix: AgreementMatrix) -> ImageAgreement {
+        ImageAgreement::new(filename.to_string(), String::new(), matrix, HashMap::new(), Vec::new())
+    }
+
+    fn close(value: Option<f64>, expected: f64) -> bool {
+        value.is_some_and(|value| (value - expected).abs() < 1e-9)
+    }
+
+    #[test]
+    fn unreadable_pairs_are_skipped() {
+        let methods = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+
+        // c 无法读取：只有 a-b 参与计算，不因缺失的配对而被排到最前
+        let mut partial = AgreementMatrix::identity(3);
+        partial.set(0, 1, 0.5, 0.6);
+        let partial = image("partial.png", partial);
+        assert!(close(partial.disagreement, 0.4));
+
+        let mut full = AgreementMatrix::identity(3);
+        full.set(0, 1, 0.1, 0.2);
+        full.set(0, 2, 0.5, 0.6);
+        full.set(1, 2, 0.5, 0.6);
+
+        let images = vec![partial, image("full.png", full), image("none.png", AgreementMatrix::identity(3))];
+        let report = build_report(methods, images);
+        let order: Vec<&str> = report.images.iter().map(|image| image.filename.as_str()).collect();
+        assert_eq!(order, ["full.png", "partial.png"]);
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(report.skipped[0].filename, "none.png");
+        assert_eq!(report.skipped[0].disagreement, None);
+
+        assert_eq!(report.pair_counts[0][1], 2);
+        assert_eq!(report.pair_counts[0][2], 1);
+        assert!(close(report.overall.dice[0][1], 0.4));
+        assert!(close(report.overall.iou[0][2], 0.5));
+    }
+}
@@ -0,0 +1,487 @@
+// 标注文件作为 GT：COCO JSON（多边形或 RLE）以及逐图的 LabelMe JSON，在后端栅格化为掩码
+
+use crate::mask::{MaskData, MaskEncoding};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::SystemTime;
+
+#[derive(Debug, Deserialize)]
+struct CocoFile {
+    images: Vec<CocoImage>,
+    #[serde(default)]
+    annotations: Vec<CocoAnnotation>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CocoImage {
+    id: u64,
+    file_name: String,
+    width: u32,
+    height: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct CocoAnnotation {
+    image_id: u64,
+    #[serde(default)]
+    category_id: u64,
+    segmentation: Option<CocoSegmentation>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum CocoSegmentation {
+    Polygons(Vec<Vec<f64>>),
+    Rle(CocoRle),
+}
+
+#[derive(Debug, Deserialize)]
+struct CocoRle {
+    size: [u32; 2],
+    counts: RleCounts,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum RleCounts {
+    Uncompressed(Vec<u64>),
+    Compressed(String),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LabelMeFile {
+    #[serde(default)]
+    shapes: Vec<LabelMeShape>,
+    image_path: Option<String>,
+    image_width: Option<u32>,
+    image_height: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LabelMeShape {
+    points: Vec<[f64; 2]>,
+    shape_type: Option<String>,
+}
+
+// 已解析的标注来源，键为图片文件名（不含目录）
+pub struct AnnotationSet {
+    source: Source,
+}
+
+enum Source {
+    Coco {
+        path: String,
+        images: HashMap<String, (u64, u32, u32)>,
+        annotations: HashMap<u64, Vec<CocoAnnotation>>,
+    },
+    LabelMe {
+        files: HashMap<String, String>,
+    },
+}
+
+// 已解析的标注来源，键为规范化路径；叠加图、缩略图与导出逐张读取 GT 时不必每次重新解析整个标注
+struct CachedSet {
+    signature: Vec<(u64, Option<SystemTime>)>,
+    set: Arc<AnnotationSet>,
+}
+
+// 最多缓存的标注来源数，超出时清空
+const MAX_CACHED_SETS: usize = 8;
+
+static CACHE: OnceLock<Mutex<HashMap<PathBuf, CachedSet>>> = OnceLock::new();
+
+// 标注来源的修改标记：COCO 为文件本身，LabelMe 为文件夹中每个 JSON 的大小与修改时间
+fn signature(path: &Path) -> Vec<(u64, Option<SystemTime>)> {
+    let stamp = |metadata: fs::Metadata| (metadata.len(), metadata.modified().ok());
+    if !path.is_dir() {
+        return fs::metadata(path).map(stamp).into_iter().collect();
+    }
+    let mut entries: Vec<(PathBuf, (u64, Option<SystemTime>))> = fs::read_dir(path)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| {
+            entry
+                .path()
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| e.eq_ignore_ascii_case("json"))
+        })
+        .filter_map(|entry| Some((entry.path(), stamp(entry.metadata().ok()?))))
+        .collect();
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    entries.into_iter().map(|(_, stamp)| stamp).collect()
+}
+
+impl AnnotationSet {
+    // 同 open，但复用之前解析的结果；标注文件修改后重新解析
+    pub fn open_cached(path: &str) -> Result<Option<Arc<AnnotationSet>>, String> {
+        let Ok(key) = Path::new(path).canonicalize() else {
+            return Ok(None);
+        };
+        let signature = signature(&key);
+        let cache = CACHE.get_or_init(Mutex::default);
+        if let Some(cached) = cache.lock().map_err(|_| "标注缓存已损坏".to_string())?.get(&key) {
+            if cached.signature == signature {
+                return Ok(Some(cached.set.clone()));
+            }
+        }
+
+        let Some(set) = AnnotationSet::open(path)? else {
+            return Ok(None);
+        };
+        let set = Arc::new(set);
+        let mut cache = cache.lock().map_err(|_| "标注缓存已损坏".to_string())?;
+        if cache.len() >= MAX_CACHED_SETS {
+            cache.clear();
+        }
+        cache.insert(
+            key,
+            CachedSet {
+                signature,
+                set: set.clone(),
+            },
+        );
+        Ok(Some(set))
+    }
+
+    // 识别标注来源：.json 文件按 COCO 解析，包含 LabelMe JSON 的文件夹按 LabelMe 解析，其余返回 None
+    pub fn open(path: &str) -> Result<Option<AnnotationSet>, String> {
+        let source = Path::new(path);
+
+        if source.is_file() {
+            if !path.to_lowercase().ends_with(".json") {
+                return Ok(None);
+            }
+            let content = fs::read_to_string(source).map_err(|e| format!("无法读取标注文件 {}: {}", path, e))?;
+            let coco: CocoFile =
+                serde_json::from_str(&content).map_err(|e| format!("无法解析 COCO 标注 {}: {}", path, e))?;
+
+            let ids: HashSet<u64> = coco.images.iter().map(|image| image.id).collect();
+            let images = coco
+                .images
+                .iter()
+                .map(|image| (file_name_of(&image.file_name), (image.id, image.width, image.height)))
+                .collect();
+            let mut annotations: HashMap<u64, Vec<CocoAnnotation>> = HashMap::new();
+            for annotation in coco.annotations {
+                if ids.contains(&annotation.image_id) {
+                    annotations.entry(annotation.image_id).or_default().push(annotation);
+                }
+            }
+
+            return Ok(Some(AnnotationSet {
+                source: Source::Coco {
+                    path: path.to_string(),
+                    images,
+                    annotations,
+                },
+            }));
+        }
+
+        if !source.is_dir() {
+            return Ok(None);
+        }
+
+        let mut files = HashMap::new();
+        let entries = fs::read_dir(source).map_err(|e| format!("无法读取文件夹 {}: {}", path, e))?;
+        for entry in entries.flatten() {
+            let json_path = entry.path();
+            let is_json = json_path
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| e.eq_ignore_ascii_case("json"));
+            if !is_json {
+                continue;
+            }
+
+            // 无法解析或不含 shapes 的 JSON 不属于 LabelMe 标注，直接跳过
+            let Ok(content) = fs::read_to_string(&json_path) else {
+                continue;
+            };
+            let Ok(labelme) = serde_json::from_str::<LabelMeFile>(&content) else {
+                continue;
+            };
+            if let Some(image_path) = labelme.image_path {
+                files.insert(file_name_of(&image_path), json_path.to_string_lossy().to_string());
+            }
+        }
+
+        if files.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(AnnotationSet {
+                source: Source::LabelMe { files },
+            }))
+        }
+    }
+
+    // 标注中出现的图片文件名，已排序
+    pub fn file_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = match &self.source {
+            Source::Coco { images, .. } => images.keys().cloned().collect(),
+            Source::LabelMe { files } => files.keys().cloned().collect(),
+        };
+        names.sort();
+        names
+    }
+
+    // 某张图片对应的标注文件路径，用于结果中的 GT 路径
+    pub fn source_path(&self, filename: &str) -> Option<String> {
+        match &self.source {
+            Source::Coco { path, images, .. } => images.get(filename).map(|_| path.clone()),
+            Source::LabelMe { files } => files.get(filename).cloned(),
+        }
+    }
+
+    // 栅格化某张图片的标注；target_size 为原始图片尺寸，未提供时使用标注中记录的尺寸
+    pub fn rasterize(&self, filename: &str, target_size: Option<(u32, u32)>) -> Result<MaskData, String> {
+        match &self.source {
+            Source::Coco {
+                images, annotations, ..
+            } => {
+                let &(id, width, height) = images
+                    .get(filename)
+                    .ok_or_else(|| format!("COCO 标注中没有图片: {}", filename))?;
+                let (target_width, target_height) = target_size.unwrap_or((width, height));
+                let scale = (
+                    target_width as f64 / width.max(1) as f64,
+                    target_height as f64 / height.max(1) as f64,
+                );
+
+                let mut data = vec![0.0f32; target_width as usize * target_height as usize];
+                for annotation in annotations.get(&id).into_iter().flatten() {
+                    // category_id 为 0 时仍视为前景
+                    let value = annotation.category_id.max(1) as f32;
+                    match &annotation.segmentation {
+                        Some(CocoSegmentation::Polygons(polygons)) => {
+                            for polygon in polygons {
+                                let points: Vec<(f64, f64)> = polygon
+                                    .chunks_exact(2)
+                                    .map(|p| (p[0] * scale.0, p[1] * scale.1))
+                                    .collect();
+                                fill_polygon(&mut data, target_width, target_height, &points, value);
+                            }
+                        }
+                        Some(CocoSegmentation::Rle(rle)) => {
+                            let decoded = decode_rle(rle)?;
+                            let [rle_height, rle_width] = rle.size;
+                            for y in 0..target_height {
+                                for x in 0..target_width {
+                                    // 尺寸不同时按最近邻采样
+                                    let sx = (x as u64 * rle_width as u64 / target_width as u64) as usize;
+                                    let sy = (y as u64 * rle_height as u64 / target_height as u64) as usize;
+                                    if decoded[sx * rle_height as usize + sy] {
+                                        data[y as usize * target_width as usize + x as usize] = value;
+                                    }
+                                }
+                            }
+                        }
+                        None => {}
+                    }
+                }
+
+                Ok(MaskData {
+                    width: target_width,
+                    height: target_height,
+                    data,
+                    encoding: MaskEncoding::Label,
+                })
+            }
+            Source::LabelMe { files } => {
+                let json_path = files
+                    .get(filename)
+                    .ok_or_else(|| format!("LabelMe 标注中没有图片: {}", filename))?;
+                let content =
+                    fs::read_to_string(json_path).map_err(|e| format!("无法读取标注文件 {}: {}", json_path, e))?;
+                let labelme: LabelMeFile = serde_json::from_str(&content)
+                    .map_err(|e| format!("无法解析 LabelMe 标注 {}: {}", json_path, e))?;
+
+                let declared = labelme.image_width.zip(labelme.image_height);
+                let (target_width, target_height) = target_size
+                    .or(declared)
+                    .ok_or_else(|| format!("无法确定图片尺寸: {}", filename))?;
+                let (width, height) = declared.unwrap_or((target_width, target_height));
+                let scale = (
+                    target_width as f64 / width.max(1) as f64,
+                    target_height as f64 / height.max(1) as f64,
+                );
+
+                let mut data = vec![0.0f32; target_width as usize * target_height as usize];
+                for shape in &labelme.shapes {
+                    let points: Vec<(f64, f64)> = shape
+                        .points
+                        .iter()
+                        .map(|p| (p[0] * scale.0, p[1] * scale.1))
+                        .collect();
+                    let polygon = match shape.shape_type.as_deref().unwrap_or("polygon") {
+                        "polygon" => points,
+                        "rectangle" if points.len() >= 2 => {
+                            let (a, b) = (points[0], points[1]);
+                            vec![(a.0, a.1), (b.0, a.1), (b.0, b.1), (a.0, b.1)]
+                        }
+                        "circle" if points.len() >= 2 => {
+                            let (center, edge) = (points[0], points[1]);
+                            let radius = ((edge.0 - center.0).powi(2) + (edge.1 - center.1).powi(2)).sqrt();
+                            (0..64)
+                                .map(|i| {
+                                    let angle = i as f64 / 64.0 * std::f64::consts::TAU;
+                                    (center.0 + radius * angle.cos(), center.1 + radius * angle.sin())
+                                })
+                                .collect()
+                        }
+                        // 线段与点不构成区域
+                        _ => continue,
+                    };
+                    fill_polygon(&mut data, target_width, target_height, &polygon, 1.0);
+                }
+
+                Ok(MaskData {
+                    width: target_width,
+                    height: target_height,
+                    data,
+                    encoding: MaskEncoding::Label,
+                })
+            }
+        }
+    }
+}
+
+fn file_name_of(path: &str) -> String {
+    // 标注中的路径可能来自 Windows
+    path.rsplit(['/', '\\']).next().unwrap_or(path).to_string()
+}
+
+// 扫描线填充（奇偶规则），以像素中心判断是否在多边形内
+fn fill_polygon(data: &mut [f32], width: u32, height: u32, points: &[(f64, f64)], value: f32) {
+    if points.len() < 3 {
+        return;
+    }
+
+    let mut crossings = Vec::new();
+    for y in 0..height {
+        let yc = y as f64 + 0.5;
+        crossings.clear();
+        for i in 0..points.len() {
+            let (x0, y0) = points[i];
+            let (x1, y1) = points[(i + 1) % points.len()];
+            if (y0 <= yc && y1 > yc) || (y1 <= yc && y0 > yc) {
+                crossings.push(x0 + (yc - y0) / (y1 - y0) * (x1 - x0));
+            }
+        }
+        crossings.sort_by(|a, b| a.total_cmp(b));
+
+        for pair in crossings.chunks_exact(2) {
+            let start = (pair[0] - 0.5).ceil().max(0.0) as u32;
+            let end = ((pair[1] - 0.5).ceil().max(0.0) as u32).min(width);
+            for x in start..end {
+                data[y as usize * width as usize + x as usize] = value;
+            }
+        }
+    }
+}
+
+// 解码 COCO RLE，结果按列优先排列
+fn decode_rle(rle: &CocoRle) -> Result<Vec<bool>, String> {
+    let [height, width] = rle.size;
+    let counts = match &rle.counts {
+        RleCounts::Uncompressed(counts) => counts.clone(),
+        RleCounts::Compressed(encoded) => decode_rle_string(encoded)?,
+    };
+
+    if width == 0 || height == 0 {
+        return Err(format!("RLE 尺寸无效: {}x{}", width, height));
+    }
+    let total = height as u64 * width as u64;
+    let mut mask = Vec::new();
+    for (i, &count) in counts.iter().enumerate() {
+        // 计数超出尺寸时提前报错，避免按损坏的计数分配内存
+        if mask.len() as u64 + count > total {
+            return Err(format!("RLE 计数超出尺寸 {}x{}", width, height));
+        }
+        // 计数从背景开始交替
+        mask.resize(mask.len() + count as usize, i % 2 == 1);
+    }
+    if mask.len() as u64 != total {
+        return Err(format!("RLE 长度 {} 与尺寸 {}x{} 不一致", mask.len(), width, height));
+    }
+    Ok(mask)
+}
+
+// COCO 压缩 RLE 字符串：每个字符携带 5 位数据，第三个计数起存储与前前一个计数的差值
+fn decode_rle_string(encoded: &str) -> Result<Vec<u64>, String> {
+    let bytes = encoded.as_bytes();
+    let mut counts: Vec<i64> = Vec::new();
+    let mut p = 0;
+
+    while p < bytes.len() {
+        let mut x: i64 = 0;
+        let mut k = 0;
+        loop {
+            // 连续字符过多时移位会超出 64 位
+            if 5 * k >= 64 {
+                return Err("RLE 编码无效: 计数过长".to_string());
+            }
+            let c = *bytes.get(p).ok_or("RLE 字符串不完整")? as i64 - 48;
+            x |= (c & 0x1f) << (5 * k);
+            p += 1;
+            k += 1;
+            if c & 0x20 == 0 {
+                if c & 0x10 != 0 && 5 * k < 64 {
+                    x |= -1 << (5 * k);
+                }
+                break;
+            }
+        }
+        if counts.len() > 2 {
+            x = x
+                .checked_add(counts[counts.len() - 2])
+                .ok_or("RLE 编码无效: 计数溢出")?;
+        }
+        counts.push(x);
+    }
+
+    counts
+        .into_iter()
+        .map(|c| u64::try_from(c).map_err(|_| "RLE 计数为负".to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rle(counts: &str, size: [u32; 2]) -> CocoRle {
+        CocoRle {
+            size,
+            counts: RleCounts::Compressed(counts.to_string()),
+        }
+    }
+
+    #[test]
+    fn decodes_compressed_rle() {
+        // 计数 3, 2, 4：按列优先展开 3x3
+        let mask = decode_rle(&rle("324", [3, 3])).unwrap();
+        assert_eq!(mask.iter().filter(|&&v| v).count(), 2);
+        assert!(!mask[2] && mask[3] && mask[4] && !mask[5]);
+    }
+
+    #[test]
+    fn rejects_continuation_overflow() {
+        // 每个 'P' 只带延续位，足够长的连续字符会让移位超出 64 位
+        let encoded = format!("{}0", "P".repeat(20));
+        assert!(decode_rle_string(&encoded).is_err());
+        assert!(decode_rle_string(&"P".repeat(20)).is_err());
+    }
+
+    #[test]
+    fn rejects_inconsistent_sizes() {
+        assert!(decode_rle(&rle("324", [0, 3])).is_err());
+        assert!(decode_rle(&rle("32", [3, 3])).is_err());
+        assert!(decode_rle(&rle("3:4", [3, 3])).is_err());
+    }
+}
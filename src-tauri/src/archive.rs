@@ -0,0 +1,75 @@
+// 导出压缩包：将导出的图片、误差叠加图与指标文件打包为一个 zip 或 tar.gz，条目路径以 / 分隔
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use zip::write::SimpleFileOptions;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchiveFormat {
+    #[default]
+    Zip,
+    TarGz,
+}
+
+impl ArchiveFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ArchiveFormat::Zip => ".zip",
+            ArchiveFormat::TarGz => ".tar.gz",
+        }
+    }
+}
+
+pub enum ArchiveWriter {
+    Zip(zip::ZipWriter<File>),
+    TarGz(tar::Builder<GzEncoder<File>>),
+}
+
+impl ArchiveWriter {
+    pub fn create(path: &Path, format: ArchiveFormat) -> Result<Self, String> {
+        let file = File::create(path).map_err(|e| format!("无法创建压缩文件 {}: {}", path.display(), e))?;
+        Ok(match format {
+            ArchiveFormat::Zip => ArchiveWriter::Zip(zip::ZipWriter::new(file)),
+            ArchiveFormat::TarGz => {
+                ArchiveWriter::TarGz(tar::Builder::new(GzEncoder::new(file, Compression::default())))
+            }
+        })
+    }
+
+    pub fn add(&mut self, name: &str, data: &[u8]) -> Result<(), String> {
+        let failed = |e: &dyn std::fmt::Display| format!("写入压缩文件失败 {}: {}", name, e);
+        match self {
+            ArchiveWriter::Zip(writer) => {
+                writer
+                    .start_file(name, SimpleFileOptions::default())
+                    .map_err(|e| failed(&e))?;
+                writer.write_all(data).map_err(|e| failed(&e))
+            }
+            ArchiveWriter::TarGz(builder) => {
+                let mut header = tar::Header::new_gnu();
+                header.set_size(data.len() as u64);
+                header.set_mode(0o644);
+                header.set_mtime(SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()));
+                header.set_cksum();
+                builder.append_data(&mut header, name, data).map_err(|e| failed(&e))
+            }
+        }
+    }
+
+    pub fn finish(self) -> Result<(), String> {
+        let failed = |e: &dyn std::fmt::Display| format!("完成压缩文件失败: {}", e);
+        match self {
+            ArchiveWriter::Zip(writer) => writer.finish().map(|_| ()).map_err(|e| failed(&e)),
+            ArchiveWriter::TarGz(builder) => {
+                let encoder = builder.into_inner().map_err(|e| failed(&e))?;
+                encoder.finish().map(|_| ()).map_err(|e| failed(&e))
+            }
+        }
+    }
+}
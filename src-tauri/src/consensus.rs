@@ -0,0 +1,275 @@
+// 多标注者 GT：标注者之间的一致性（两两 Dice、Fleiss' kappa），以及多数投票与 STAPLE 融合得到的共识 GT
+
+use crate::mask::{MaskData, MaskEncoding};
+use serde::{Deserialize, Serialize};
+
+// 共识 GT 在结果中的键，与标注者的角色 ID 并列
+pub const MAJORITY_VOTE: &str = "majority_vote";
+pub const STAPLE: &str = "staple";
+
+// STAPLE 期望最大化的迭代上限与收敛阈值
+const STAPLE_MAX_ITERATIONS: usize = 50;
+const STAPLE_TOLERANCE: f64 = 1e-6;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PairAgreement {
+    pub a: String,
+    pub b: String,
+    pub dice: f64,
+}
+
+// STAPLE 估计的单个标注者的敏感度与特异度
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RaterPerformance {
+    pub id: String,
+    pub sensitivity: f64,
+    pub specificity: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnnotatorAgreement {
+    pub pairwise: Vec<PairAgreement>,
+    pub mean_dice: f64,
+    pub fleiss_kappa: f64,
+    pub raters: Vec<RaterPerformance>,
+}
+
+// 模型相对某个参考（标注者或共识 GT）的指标
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReferenceScore {
+    pub reference: String,
+    pub iou: f64,
+    pub dice: f64,
+    pub accuracy: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MethodReferenceScores {
+    pub scores: Vec<ReferenceScore>,
+    // 模型相对各标注者的平均 Dice
+    pub mean_annotator_dice: f64,
+    // 模型与标注者的平均一致性不低于标注者之间的平均一致性
+    pub within_human_variability: bool,
+}
+
+// 一致性分析与两种共识 GT
+pub struct Consensus {
+    pub agreement: AnnotatorAgreement,
+    pub majority_vote: MaskData,
+    pub staple: MaskData,
+}
+
+// 分析多个标注者的掩码；尺寸不同的掩码缩放到第一个标注者的尺寸，按前景/背景二值比较
+pub fn analyze(annotators: &[(String, MaskData)]) -> Option<Consensus> {
+    let (_, first) = annotators.first()?;
+    let (width, height) = (first.width, first.height);
+
+    let decisions: Vec<Vec<bool>> = annotators
+        .iter()
+        .map(|(_, mask)| {
+            let mask = if mask.width != width || mask.height != height {
+                mask.clone().resize(width, height)
+            } else {
+                mask.clone()
+            };
+            let threshold = mask.encoding.threshold();
+            mask.data.iter().map(|&v| v > threshold).collect()
+        })
+        .collect();
+
+    let mut pairwise = Vec::new();
+    for i in 0..decisions.len() {
+        for j in i + 1..decisions.len() {
+            pairwise.push(PairAgreement {
+                a: annotators[i].0.clone(),
+                b: annotators[j].0.clone(),
+                dice: dice(&decisions[i], &decisions[j]),
+            });
+        }
+    }
+    let mean_dice = if pairwise.is_empty() {
+        1.0
+    } else {
+        pairwise.iter().map(|p| p.dice).sum::<f64>() / pairwise.len() as f64
+    };
+
+    // 每个像素标为前景的标注者数量
+    let pixel_count = width as usize * height as usize;
+    let votes: Vec<usize> = (0..pixel_count)
+        .map(|i| decisions.iter().filter(|d| d[i]).count())
+        .collect();
+
+    // 多数投票：超过半数的标注者标为前景，票数相同时记为背景
+    let majority: Vec<bool> = votes.iter().map(|&v| v * 2 > decisions.len()).collect();
+    let (staple, raters) = staple(&decisions);
+
+    let to_mask = |foreground: &[bool]| MaskData {
+        width,
+        height,
+        data: foreground.iter().map(|&f| if f { 1.0 } else { 0.0 }).collect(),
+        encoding: MaskEncoding::Label,
+    };
+
+    Some(Consensus {
+        agreement: AnnotatorAgreement {
+            pairwise,
+            mean_dice,
+            fleiss_kappa: fleiss_kappa(&votes, decisions.len()),
+            raters: annotators
+                .iter()
+                .zip(raters)
+                .map(|((id, _), (sensitivity, specificity))| RaterPerformance {
+                    id: id.clone(),
+                    sensitivity,
+                    specificity,
+                })
+                .collect(),
+        },
+        majority_vote: to_mask(&majority),
+        staple: to_mask(&staple),
+    })
+}
+
+fn dice(a: &[bool], b: &[bool]) -> f64 {
+    let intersection = a.iter().zip(b).filter(|(&x, &y)| x && y).count();
+    let total = a.iter().filter(|&&x| x).count() + b.iter().filter(|&&y| y).count();
+    if total == 0 {
+        1.0 // 两者都没有前景时认为完全一致
+    } else {
+        2.0 * intersection as f64 / total as f64
+    }
+}
+
+// 前景/背景两类的 Fleiss' kappa，votes 为每个像素标为前景的标注者数量
+fn fleiss_kappa(votes: &[usize], raters: usize) -> f64 {
+    if raters < 2 || votes.is_empty() {
+        return 1.0;
+    }
+    let n = raters as f64;
+    let pixels = votes.len() as f64;
+
+    let mut observed = 0.0;
+    let mut foreground = 0.0;
+    for &v in votes {
+        let (fg, bg) = (v as f64, n - v as f64);
+        observed += (fg * fg + bg * bg - n) / (n * (n - 1.0));
+        foreground += fg;
+    }
+    let observed = observed / pixels;
+    let p = foreground / (pixels * n);
+    let expected = p * p + (1.0 - p) * (1.0 - p);
+
+    if (1.0 - expected).abs() < f64::EPSILON {
+        1.0 // 所有标注者的所有像素都属于同一类
+    } else {
+        (observed - expected) / (1.0 - expected)
+    }
+}
+
+// 二值 STAPLE（Warfield 等，2004）：以期望最大化同时估计真实分割与每个标注者的敏感度/特异度
+// 前景先验取所有标注中前景的比例；返回真实分割（后验概率 > 0.5）与每个标注者的 (敏感度, 特异度)
+fn staple(decisions: &[Vec<bool>]) -> (Vec<bool>, Vec<(f64, f64)>) {
+    let pixel_count = decisions.first().map_or(0, Vec::len);
+    let total = (pixel_count * decisions.len()).max(1) as f64;
+    let prior = decisions.iter().flatten().filter(|&&d| d).count() as f64 / total;
+    // 先验为 0 或 1 时后验恒定，夹到开区间内以免除零
+    let prior = prior.clamp(1e-6, 1.0 - 1e-6);
+
+    let mut performance = vec![(0.99, 0.99); decisions.len()];
+    let mut weights = vec![prior; pixel_count];
+
+    for _ in 0..STAPLE_MAX_ITERATIONS {
+        // E 步：每个像素为前景的后验概率
+        let mut change = 0.0;
+        for (i, weight) in weights.iter_mut().enumerate() {
+            let (mut a, mut b) = (prior, 1.0 - prior);
+            for (rater, &(sensitivity, specificity)) in decisions.iter().zip(&performance) {
+                if rater[i] {
+                    a *= sensitivity;
+                    b *= 1.0 - specificity;
+                } else {
+                    a *= 1.0 - sensitivity;
+                    b *= specificity;
+                }
+            }
+            let updated = if a + b > 0.0 { a / (a + b) } else { prior };
+            change += (updated - *weight).abs();
+            *weight = updated;
+        }
+
+        // M 步：按后验概率加权更新每个标注者的敏感度与特异度
+        let foreground_weight: f64 = weights.iter().sum();
+        let background_weight = pixel_count as f64 - foreground_weight;
+        for (rater, performance) in decisions.iter().zip(performance.iter_mut()) {
+            let (mut true_positive, mut true_negative) = (0.0, 0.0);
+            for (&decision, &weight) in rater.iter().zip(&weights) {
+                if decision {
+                    true_positive += weight;
+                } else {
+                    true_negative += 1.0 - weight;
+                }
+            }
+            if foreground_weight > 0.0 {
+                performance.0 = true_positive / foreground_weight;
+            }
+            if background_weight > 0.0 {
+                performance.1 = true_negative / background_weight;
+            }
+        }
+
+        if change / pixel_count.max(1) as f64 <= STAPLE_TOLERANCE {
+            break;
+        }
+    }
+
+    (weights.iter().map(|&w| w > 0.5).collect(), performance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mask(values: &[u8]) -> MaskData {
+        MaskData {
+            width: values.len() as u32,
+            height: 1,
+            data: values.iter().map(|&v| v as f32).collect(),
+            encoding: MaskEncoding::Label,
+        }
+    }
+
+    #[test]
+    fn identical_annotators_agree_fully() {
+        let annotators = vec![
+            ("a".to_string(), mask(&[0, 1, 1, 0])),
+            ("b".to_string(), mask(&[0, 1, 1, 0])),
+        ];
+        let consensus = analyze(&annotators).unwrap();
+        assert_eq!(consensus.agreement.mean_dice, 1.0);
+        assert!((consensus.agreement.fleiss_kappa - 1.0).abs() < 1e-9);
+        assert_eq!(consensus.staple.data, vec![0.0, 1.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn majority_vote_breaks_ties_to_background() {
+        let annotators = vec![
+            ("a".to_string(), mask(&[1, 1, 0, 0])),
+            ("b".to_string(), mask(&[1, 0, 1, 0])),
+        ];
+        let consensus = analyze(&annotators).unwrap();
+        assert_eq!(consensus.majority_vote.data, vec![1.0, 0.0, 0.0, 0.0]);
+        assert!(consensus.agreement.fleiss_kappa < 1.0);
+    }
+
+    #[test]
+    fn staple_follows_the_reliable_majority() {
+        let decisions = vec![
+            vec![true, true, false, false],
+            vec![true, true, false, false],
+            vec![true, false, true, false],
+        ];
+        let (truth, performance) = staple(&decisions);
+        assert_eq!(truth, vec![true, true, false, false]);
+        assert!(performance[0].0 > performance[2].0);
+    }
+}
@@ -0,0 +1,152 @@
+// 轮廓叠加图：提取 GT 与各模型预测掩码的边界，以不同颜色与线宽画在原始图片上，不遮挡前景内部
+
+use crate::mask::MaskData;
+use image::imageops::FilterType;
+use image::{DynamicImage, Rgb, RgbImage};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// 未单独设置颜色的模型按先后依次使用的颜色，与 GT 的绿色区分
+const MODEL_COLORS: [[u8; 3]; 8] = [
+    [230, 0, 0],
+    [0, 120, 255],
+    [255, 200, 0],
+    [230, 0, 230],
+    [0, 220, 220],
+    [255, 120, 0],
+    [140, 80, 255],
+    [255, 255, 255],
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContourOptions {
+    #[serde(default = "default_gt_color")]
+    pub gt_color: [u8; 3],
+    // 线宽以掩码像素计
+    #[serde(default = "default_width")]
+    pub gt_width: u32,
+    #[serde(default = "default_visible")]
+    pub show_gt: bool,
+    // 未单独设置线宽的模型使用的线宽
+    #[serde(default = "default_width")]
+    pub pred_width: u32,
+    // 按模型设置颜色、线宽与是否显示
+    #[serde(default)]
+    pub models: HashMap<String, ContourStyle>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContourStyle {
+    #[serde(default)]
+    pub color: Option<[u8; 3]>,
+    #[serde(default)]
+    pub width: Option<u32>,
+    #[serde(default = "default_visible")]
+    pub visible: bool,
+}
+
+// 参与绘制的预测：模型名称与预测文件路径，颜色按在列表中的位置分配
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContourPrediction {
+    pub model: String,
+    pub path: String,
+}
+
+fn default_gt_color() -> [u8; 3] {
+    [0, 200, 0]
+}
+
+fn default_width() -> u32 {
+    2
+}
+
+fn default_visible() -> bool {
+    true
+}
+
+impl Default for ContourOptions {
+    fn default() -> Self {
+        ContourOptions {
+            gt_color: default_gt_color(),
+            gt_width: default_width(),
+            show_gt: default_visible(),
+            pred_width: default_width(),
+            models: HashMap::new(),
+        }
+    }
+}
+
+impl ContourOptions {
+    pub fn visible(&self, model: &str) -> bool {
+        self.models.get(model).is_none_or(|style| style.visible)
+    }
+
+    // 列表中第 index 个模型的颜色与线宽；隐藏的模型也占用位置，切换显示时其余模型的颜色不变
+    pub fn style(&self, model: &str, index: usize) -> ([u8; 3], u32) {
+        let style = self.models.get(model);
+        (
+            style.and_then(|s| s.color).unwrap_or(MODEL_COLORS[index % MODEL_COLORS.len()]),
+            style.and_then(|s| s.width).unwrap_or(self.pred_width),
+        )
+    }
+}
+
+// 边界像素：前景中上下左右有背景的像素，图片边缘之外视为背景
+pub fn boundary(mask: &MaskData) -> Vec<(u32, u32)> {
+    let (width, height) = (mask.width, mask.height);
+    let background = |x: i64, y: i64| {
+        x < 0 || y < 0 || x >= width as i64 || y >= height as i64 || !mask.is_foreground(x as u32, y as u32)
+    };
+    (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .filter(|&(x, y)| mask.is_foreground(x, y))
+        .filter(|&(x, y)| {
+            let (x, y) = (x as i64, y as i64);
+            background(x - 1, y) || background(x + 1, y) || background(x, y - 1) || background(x, y + 1)
+        })
+        .collect()
+}
+
+// 在与掩码同尺寸的图片上画出边界，线宽大于 1 时向内外两侧加粗
+pub fn draw(image: &mut RgbImage, mask: &MaskData, color: [u8; 3], width: u32) {
+    let width = width.max(1) as i64;
+    let (before, after) = ((width - 1) / 2, width / 2);
+    let (image_width, image_height) = (image.width() as i64, image.height() as i64);
+    for (x, y) in boundary(mask) {
+        for dy in -before..=after {
+            for dx in -before..=after {
+                let (px, py) = (x as i64 + dx, y as i64 + dy);
+                if px >= 0 && py >= 0 && px < image_width && py < image_height {
+                    image.put_pixel(px as u32, py as u32, Rgb(color));
+                }
+            }
+        }
+    }
+}
+
+// 掩码须与 GT 尺寸一致，predictions 为 (在列表中的位置, 模型名称, 掩码)；
+// 原始图片缩放到掩码尺寸作为底图，没有原始图片时为黑色；先画 GT 再按顺序画各模型，重叠处显示后画的
+pub fn render_contours(
+    gt: &MaskData,
+    predictions: &[(usize, &str, MaskData)],
+    original: Option<&DynamicImage>,
+    options: &ContourOptions,
+) -> DynamicImage {
+    let mut image = match original {
+        Some(img) if img.width() != gt.width || img.height() != gt.height => {
+            img.resize_exact(gt.width, gt.height, FilterType::Triangle).to_rgb8()
+        }
+        Some(img) => img.to_rgb8(),
+        None => RgbImage::new(gt.width, gt.height),
+    };
+    if options.show_gt {
+        draw(&mut image, gt, options.gt_color, options.gt_width);
+    }
+    for (index, model, mask) in predictions {
+        if options.visible(model) {
+            let (color, width) = options.style(model, *index);
+            draw(&mut image, mask, color, width);
+        }
+    }
+    DynamicImage::ImageRgb8(image)
+}
@@ -0,0 +1,770 @@
+// 导出计划：按布局与命名模板确定每个导出文件的位置，在写入任何文件之前检测重名，也可只预览不执行
+
+use crate::archive::ArchiveFormat;
+use crate::contour::{ContourOptions, ContourPrediction};
+use crate::matching;
+use crate::overlay::OverlayOptions;
+use crate::palette::Palette;
+use crate::registry::FolderRegistry;
+use crate::roles::{RoleKeys, RoleSet};
+use crate::scan;
+use crate::volume;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+// 命名模板的占位符：{ext} 为带点的扩展名，{group} 为递归扫描时的子文件夹，指标保留 4 位小数，缺失时为 NA
+pub const DEFAULT_TEMPLATE: &str = "{model}_{stem}{ext}";
+pub const DEFAULT_OVERLAY_TEMPLATE: &str = "{model}_overlay_{stem}.png";
+pub const DEFAULT_CONTOUR_TEMPLATE: &str = "{stem}_contours.png";
+// 轮廓叠加图包含全部模型，按此名称参与布局与命名模板中的 {model}
+pub const CONTOUR_MODEL: &str = "contours";
+const PLACEHOLDERS: [&str; 7] = ["model", "stem", "ext", "group", "iou", "accuracy", "dice"];
+
+#[derive(Debug, Deserialize)]
+pub struct ExportImageRequest {
+    pub export_folder: String,
+    pub image_files: Vec<ExportImageInfo>,
+    // 提供时为每个预测额外导出误差叠加图
+    #[serde(default)]
+    pub error_overlay: Option<ExportOverlayOptions>,
+    // 提供时为每张图片额外导出一张 GT 与各模型预测的轮廓叠加图
+    #[serde(default)]
+    pub contour_overlay: Option<ExportContourOptions>,
+    #[serde(default)]
+    pub layout: ExportLayout,
+    // 复制文件与误差叠加图的文件名模板，为空时使用默认模板
+    #[serde(default)]
+    pub naming_template: Option<String>,
+    #[serde(default)]
+    pub overlay_template: Option<String>,
+    #[serde(default)]
+    pub contour_template: Option<String>,
+    #[serde(default)]
+    pub on_collision: CollisionPolicy,
+    // 提供时不写入导出文件夹，而是在其中生成一个压缩文件
+    #[serde(default)]
+    pub archive: Option<ArchiveOptions>,
+    // 叠加图未给出 GT 与原始图片的键时从角色配置中取得
+    #[serde(default)]
+    pub roles: Option<RoleSet>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ArchiveOptions {
+    #[serde(default)]
+    pub format: ArchiveFormat,
+    // 压缩文件名（不含扩展名），默认 export_年月日_时分秒
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub sidecar: SidecarFormat,
+}
+
+// 压缩包中的指标文件：每个导出文件一行，记录对应模型在该图片上的 IOU、准确率与 Dice
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SidecarFormat {
+    #[default]
+    Csv,
+    Json,
+    Both,
+}
+
+pub const SIDECAR_NAME: &str = "metrics";
+
+#[derive(Debug, Deserialize)]
+pub struct ExportOverlayOptions {
+    // model_paths 中 GT 与原始图片对应的键，默认取自请求的 roles；原始图片的键为空或不存在时不叠加原图
+    #[serde(default)]
+    pub gt_key: Option<String>,
+    #[serde(default)]
+    pub original_key: Option<String>,
+    #[serde(default)]
+    pub palette: Option<Palette>,
+    #[serde(default)]
+    pub options: OverlayOptions,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExportContourOptions {
+    #[serde(default)]
+    pub gt_key: Option<String>,
+    #[serde(default)]
+    pub original_key: Option<String>,
+    #[serde(default)]
+    pub palette: Option<Palette>,
+    #[serde(default)]
+    pub options: ContourOptions,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExportImageInfo {
+    pub filename: String,
+    pub model_paths: HashMap<String, String>, // 模型名称到路径的映射
+    // 命名模板中使用的指标，通常取自 ComparisonResult
+    #[serde(default)]
+    pub iou_scores: HashMap<String, f64>,
+    #[serde(default)]
+    pub accuracy_scores: HashMap<String, f64>,
+    #[serde(default)]
+    pub dice_scores: HashMap<String, f64>,
+}
+
+// 每张图片一个文件夹（按原文件名，递归扫描时保留层级）、每个模型一个文件夹，或全部放在导出文件夹中
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportLayout {
+    #[default]
+    PerImage,
+    PerModel,
+    Flat,
+}
+
+// 目标位置已有文件或多个文件导出到同一位置时的处理方式，默认报错且不写入任何文件
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CollisionPolicy {
+    #[default]
+    Fail,
+    Skip,
+    Rename,
+    Overwrite,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationKind {
+    Copy,
+    Overlay,
+    Contour,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportOperation {
+    pub kind: OperationKind,
+    pub filename: String,
+    pub model: String,
+    pub source: String,
+    pub target: String,
+    // 目标文件已存在，执行时将被覆盖
+    pub overwrite: bool,
+    // 误差叠加图使用的 GT 与原始图片
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gt_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub original_path: Option<String>,
+    // 轮廓叠加图中的各模型预测，按模型名称排序
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub predictions: Vec<ContourPrediction>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportCollision {
+    pub target: String,
+    // 计划写入该位置的源文件
+    pub sources: Vec<String>,
+    // 目标位置已有文件
+    pub exists: bool,
+    // 已按冲突策略处理；为 false 时导出不会执行
+    pub resolved: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportPlan {
+    // 规范化后的导出文件夹
+    pub export_folder: String,
+    // 导出为压缩包时的压缩文件路径
+    pub archive: Option<String>,
+    pub operations: Vec<ExportOperation>,
+    pub collisions: Vec<ExportCollision>,
+    // 无法导出的文件，如源文件不存在或缺少生成误差叠加图所需的 GT
+    pub errors: Vec<String>,
+    // 压缩包根目录中指标文件的名称（不含扩展名），与导出的文件重名时依次改为 metrics_1、metrics_2……
+    pub sidecar_name: Option<String>,
+}
+
+impl ExportPlan {
+    pub fn unresolved(&self) -> Vec<&ExportCollision> {
+        self.collisions.iter().filter(|c| !c.resolved).collect()
+    }
+
+    // 文件在压缩包中的路径：相对导出文件夹，以 / 分隔
+    // 不与任何条目重名（不区分大小写）的指标文件名
+    fn free_sidecar_name(&self) -> String {
+        let entries: HashSet<String> = self
+            .operations
+            .iter()
+            .map(|operation| self.entry_name(operation).to_lowercase())
+            .collect();
+        (0..)
+            .map(|i| match i {
+                0 => SIDECAR_NAME.to_string(),
+                i => format!("{}_{}", SIDECAR_NAME, i),
+            })
+            .find(|name| ["csv", "json"].iter().all(|ext| !entries.contains(&format!("{}.{}", name, ext))))
+            .unwrap_or_else(|| SIDECAR_NAME.to_string())
+    }
+
+    pub fn entry_name(&self, operation: &ExportOperation) -> String {
+        let target = Path::new(&operation.target);
+        let relative = target.strip_prefix(&self.export_folder).unwrap_or(target);
+        relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportRecord {
+    pub path: String,
+    pub filename: String,
+    pub model: String,
+    pub kind: OperationKind,
+    pub iou: Option<f64>,
+    pub accuracy: Option<f64>,
+    pub dice: Option<f64>,
+}
+
+// 每个导出文件的指标，取自请求中对应图片与模型的结果；GT 与原始图片没有指标
+pub fn records(plan: &ExportPlan, request: &ExportImageRequest) -> Vec<ExportRecord> {
+    let images: HashMap<&str, &ExportImageInfo> =
+        request.image_files.iter().map(|image| (image.filename.as_str(), image)).collect();
+    plan.operations
+        .iter()
+        .map(|operation| {
+            let image = images.get(operation.filename.as_str());
+            let metric = |scores: fn(&ExportImageInfo) -> &HashMap<String, f64>| {
+                image.and_then(|image| scores(image).get(&operation.model).copied())
+            };
+            ExportRecord {
+                path: plan.entry_name(operation),
+                filename: operation.filename.clone(),
+                model: operation.model.clone(),
+                kind: operation.kind,
+                iou: metric(|image| &image.iou_scores),
+                accuracy: metric(|image| &image.accuracy_scores),
+                dice: metric(|image| &image.dice_scores),
+            }
+        })
+        .collect()
+}
+
+// 按格式生成指标文件的 (文件名, 内容)
+pub fn sidecar_files(
+    records: &[ExportRecord],
+    format: SidecarFormat,
+    name: &str,
+) -> Result<Vec<(String, Vec<u8>)>, String> {
+    let mut files = Vec::new();
+    if matches!(format, SidecarFormat::Csv | SidecarFormat::Both) {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        for record in records {
+            writer.serialize(record).map_err(|e| format!("生成 CSV 失败: {}", e))?;
+        }
+        let data = writer.into_inner().map_err(|e| format!("生成 CSV 失败: {}", e))?;
+        files.push((format!("{}.csv", name), data));
+    }
+    if matches!(format, SidecarFormat::Json | SidecarFormat::Both) {
+        let data = serde_json::to_vec_pretty(records).map_err(|e| format!("生成 JSON 失败: {}", e))?;
+        files.push((format!("{}.json", name), data));
+    }
+    Ok(files)
+}
+
+// 模板中的名称去除路径分隔符
+fn safe_name(name: &str) -> String {
+    name.replace(['/', '\\', ':'], "_")
+}
+
+fn check_template(template: &str) -> Result<(), String> {
+    let placeholder = Regex::new(r"\{([^{}]*)\}").map_err(|e| e.to_string())?;
+    for capture in placeholder.captures_iter(template) {
+        if !PLACEHOLDERS.contains(&&capture[1]) {
+            return Err(format!(
+                "命名模板包含未知的占位符 {{{}}}，可用: {}",
+                &capture[1],
+                PLACEHOLDERS.map(|p| format!("{{{}}}", p)).join(" ")
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn render_template(template: &str, values: &HashMap<&str, String>) -> Result<String, String> {
+    let mut name = template.to_string();
+    for (key, value) in values {
+        name = name.replace(&format!("{{{}}}", key), value);
+    }
+    if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+        return Err(format!("命名模板只能生成文件名: {} -> {}", template, name));
+    }
+    Ok(name)
+}
+
+impl ExportLayout {
+    fn folder(self, export_folder: &Path, filename: &str, model: &str) -> PathBuf {
+        let (parent, name) = filename.rsplit_once('/').unwrap_or(("", filename));
+        match self {
+            ExportLayout::PerImage => export_folder.join(parent).join(name.replace('.', "_")),
+            ExportLayout::PerModel => export_folder.join(safe_name(model)).join(parent),
+            ExportLayout::Flat => export_folder.to_path_buf(),
+        }
+    }
+}
+
+fn template_values(image: &ExportImageInfo, model: &str, stem: &str, ext: &str) -> HashMap<&'static str, String> {
+    let metric = |scores: &HashMap<String, f64>| {
+        scores
+            .get(model)
+            .map_or_else(|| "NA".to_string(), |v| format!("{:.4}", v))
+    };
+    HashMap::from([
+        ("model", safe_name(model)),
+        ("stem", safe_name(stem)),
+        ("ext", ext.to_string()),
+        ("group", safe_name(scan::parent_folder(&image.filename))),
+        ("iou", metric(&image.iou_scores)),
+        ("accuracy", metric(&image.accuracy_scores)),
+        ("dice", metric(&image.dice_scores)),
+    ])
+}
+
+// 在同一文件夹中找一个未被占用的文件名：name_1.ext、name_2.ext ...；on_disk 为 false 时不考虑磁盘上已有的文件
+fn renamed(target: &Path, taken: &HashSet<PathBuf>, on_disk: bool) -> PathBuf {
+    let name = target.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let (stem, ext) = matching::split_extension(&name);
+    (1..)
+        .map(|i| target.with_file_name(format!("{}_{}{}", stem, i, ext)))
+        .find(|candidate| !(taken.contains(candidate) || on_disk && candidate.exists()))
+        .expect("总能找到未占用的文件名")
+}
+
+// 写入单个文件的位置：须位于导出文件夹中；已存在时按冲突策略报错、跳过（返回 None）、重命名或覆盖
+pub fn output_path(registry: &FolderRegistry, path: &Path, policy: CollisionPolicy) -> Result<Option<PathBuf>, String> {
+    let path = registry.resolve_output(path)?;
+    if !path.exists() {
+        return Ok(Some(path));
+    }
+    match policy {
+        CollisionPolicy::Fail => Err(format!("文件已存在: {}", path.display())),
+        CollisionPolicy::Skip => Ok(None),
+        CollisionPolicy::Rename => Ok(Some(renamed(&path, &HashSet::new(), true))),
+        CollisionPolicy::Overwrite => Ok(Some(path)),
+    }
+}
+
+// 压缩文件的位置；已存在时只能按冲突策略重命名或覆盖
+fn archive_path(
+    export_folder: &Path,
+    options: &ArchiveOptions,
+    policy: CollisionPolicy,
+    registry: &FolderRegistry,
+) -> Result<PathBuf, String> {
+    let name = match &options.name {
+        Some(name) if !name.is_empty() && name != ".." && !name.contains(['/', '\\']) => name.clone(),
+        Some(name) => return Err(format!("无效的压缩文件名: {}", name)),
+        None => chrono::Local::now().format("export_%Y%m%d_%H%M%S").to_string(),
+    };
+    let path = export_folder.join(format!("{}{}", name, options.format.extension()));
+    output_path(registry, &path, policy)?.ok_or_else(|| format!("压缩文件已存在: {}", path.display()))
+}
+
+// 计算导出计划，不写入任何文件；所有源文件须位于当前实验中，目标须位于导出文件夹中
+pub fn plan(request: &ExportImageRequest, registry: &FolderRegistry) -> Result<ExportPlan, String> {
+    let export_folder = Path::new(&request.export_folder);
+    if !export_folder.exists() {
+        return Err("导出文件夹不存在".to_string());
+    }
+    let export_folder = registry.resolve_output(export_folder)?;
+    let naming_template = request.naming_template.as_deref().unwrap_or(DEFAULT_TEMPLATE);
+    let overlay_template = request.overlay_template.as_deref().unwrap_or(DEFAULT_OVERLAY_TEMPLATE);
+    check_template(naming_template)?;
+    let contour_template = request.contour_template.as_deref().unwrap_or(DEFAULT_CONTOUR_TEMPLATE);
+    check_template(overlay_template)?;
+    check_template(contour_template)?;
+    // 导出为压缩包时不写入导出文件夹，只检查计划中的文件之间是否重名
+    let on_disk = request.archive.is_none();
+    let archive = match &request.archive {
+        Some(options) => Some(archive_path(&export_folder, options, request.on_collision, registry)?),
+        None => None,
+    };
+
+    let keys = |gt_key: &Option<String>, original_key: &Option<String>| {
+        RoleKeys::resolve(gt_key.as_deref(), original_key.as_deref(), request.roles.as_ref())
+    };
+    let overlay_keys = match &request.error_overlay {
+        Some(options) => Some(keys(&options.gt_key, &options.original_key)?),
+        None => None,
+    };
+    let contour_keys = match &request.contour_overlay {
+        Some(options) => Some(keys(&options.gt_key, &options.original_key)?),
+        None => None,
+    };
+
+    let mut candidates = Vec::new();
+    let mut errors = Vec::new();
+    for image in &request.image_files {
+        let name = image.filename.rsplit('/').next().unwrap_or(&image.filename);
+        let stem = matching::split_extension(name).0;
+
+        // 源文件先规范化，之后只读取规范化后的路径，避免检查之后被替换为指向别处的符号链接
+        let mut model_paths = HashMap::new();
+        for (model, path) in &image.model_paths {
+            if !Path::new(path).exists() {
+                errors.push(format!("源文件不存在: {}", path));
+                continue;
+            }
+            match registry.resolve(path) {
+                Ok(resolved) => {
+                    model_paths.insert(model.clone(), resolved.to_string_lossy().to_string());
+                }
+                Err(e) => errors.push(e),
+            }
+        }
+
+        let mut models: Vec<(&String, &String)> = model_paths.iter().collect();
+        models.sort();
+        for (model, source_path) in models {
+            let source = Path::new(source_path);
+
+            // 按文件名匹配时各文件夹的扩展名可能不同，扩展名取自源文件
+            let source_name = source.file_name().and_then(|n| n.to_str()).unwrap_or(name);
+            let folder = request.layout.folder(&export_folder, &image.filename, model);
+            let values = template_values(image, model, stem, matching::split_extension(source_name).1);
+            let target = folder.join(render_template(naming_template, &values)?);
+            candidates.push(ExportOperation {
+                kind: OperationKind::Copy,
+                filename: image.filename.clone(),
+                model: model.clone(),
+                source: source_path.clone(),
+                target: target.to_string_lossy().to_string(),
+                overwrite: false,
+                gt_path: None,
+                original_path: None,
+                predictions: Vec::new(),
+            });
+
+            // 预测的误差叠加图，GT、原始图片与体数据不导出
+            let Some(overlay_keys) = &overlay_keys else {
+                continue;
+            };
+            let original_key = overlay_keys.original.as_deref();
+            if *model == overlay_keys.gt
+                || Some(model.as_str()) == original_key
+                || volume::is_volume_file(source_path)
+            {
+                continue;
+            }
+            let Some(gt_path) = model_paths.get(&overlay_keys.gt) else {
+                errors.push(format!("缺少GT路径，无法生成误差叠加图: {}", image.filename));
+                continue;
+            };
+            let original_path = original_key.and_then(|key| model_paths.get(key));
+            let target = folder.join(render_template(overlay_template, &values)?);
+            candidates.push(ExportOperation {
+                kind: OperationKind::Overlay,
+                filename: image.filename.clone(),
+                model: model.clone(),
+                source: source_path.clone(),
+                target: target.to_string_lossy().to_string(),
+                overwrite: false,
+                gt_path: Some(gt_path.clone()),
+                original_path: original_path.cloned(),
+                predictions: Vec::new(),
+            });
+        }
+
+        // 每张图片一张轮廓叠加图，包含除 GT、原始图片与体数据之外的全部预测
+        let Some(contour_keys) = &contour_keys else {
+            continue;
+        };
+        let original_key = contour_keys.original.as_deref();
+        let Some(gt_path) = model_paths.get(&contour_keys.gt) else {
+            errors.push(format!("缺少GT路径，无法生成轮廓叠加图: {}", image.filename));
+            continue;
+        };
+        let original_path = original_key.and_then(|key| model_paths.get(key));
+        let mut predictions: Vec<ContourPrediction> = model_paths
+            .iter()
+            .filter(|(model, path)| {
+                **model != contour_keys.gt
+                    && Some(model.as_str()) != original_key
+                    && !volume::is_volume_file(path)
+            })
+            .map(|(model, path)| ContourPrediction {
+                model: model.clone(),
+                path: path.clone(),
+            })
+            .collect();
+        predictions.sort_by(|a, b| a.model.cmp(&b.model));
+        let folder = request.layout.folder(&export_folder, &image.filename, CONTOUR_MODEL);
+        let values = template_values(image, CONTOUR_MODEL, stem, ".png");
+        let target = folder.join(render_template(contour_template, &values)?);
+        candidates.push(ExportOperation {
+            kind: OperationKind::Contour,
+            filename: image.filename.clone(),
+            model: CONTOUR_MODEL.to_string(),
+            source: gt_path.clone(),
+            target: target.to_string_lossy().to_string(),
+            overwrite: false,
+            gt_path: Some(gt_path.clone()),
+            original_path: original_path.cloned(),
+            predictions,
+        });
+    }
+
+    // 规范化目标路径后按位置分组，保持计划中的先后顺序
+    let mut groups: Vec<(PathBuf, Vec<ExportOperation>)> = Vec::new();
+    let mut group_index: HashMap<PathBuf, usize> = HashMap::new();
+    for mut candidate in candidates {
+        let target = match registry.resolve_output(Path::new(&candidate.target)) {
+            Ok(target) => target,
+            Err(e) => {
+                errors.push(e);
+                continue;
+            }
+        };
+        candidate.target = target.to_string_lossy().to_string();
+        match group_index.get(&target) {
+            Some(&index) => groups[index].1.push(candidate),
+            None => {
+                group_index.insert(target.clone(), groups.len());
+                groups.push((target, vec![candidate]));
+            }
+        }
+    }
+
+    let (operations, collisions) = resolve_collisions(groups, request.on_collision, on_disk);
+    let mut plan = ExportPlan {
+        export_folder: export_folder.to_string_lossy().to_string(),
+        archive: archive.map(|path| path.to_string_lossy().to_string()),
+        operations,
+        collisions,
+        errors,
+        sidecar_name: None,
+    };
+    if plan.archive.is_some() {
+        plan.sidecar_name = Some(plan.free_sidecar_name());
+    }
+    Ok(plan)
+}
+
+// 按冲突策略处理按目标位置分组的操作，返回执行的操作与冲突列表；on_disk 为 false 时不考虑磁盘上已有的文件
+fn resolve_collisions(
+    groups: Vec<(PathBuf, Vec<ExportOperation>)>,
+    policy: CollisionPolicy,
+    on_disk: bool,
+) -> (Vec<ExportOperation>, Vec<ExportCollision>) {
+    let mut taken: HashSet<PathBuf> = groups.iter().map(|(target, _)| target.clone()).collect();
+    let mut operations = Vec::new();
+    let mut collisions = Vec::new();
+    for (target, mut group) in groups {
+        let exists = on_disk && target.exists();
+        if group.len() == 1 && !exists {
+            operations.extend(group);
+            continue;
+        }
+
+        let sources = group.iter().map(|op| op.source.clone()).collect();
+        let resolved = match policy {
+            CollisionPolicy::Fail => {
+                for op in &mut group {
+                    op.overwrite = exists;
+                }
+                operations.extend(group);
+                false
+            }
+            CollisionPolicy::Skip => {
+                if !exists {
+                    operations.push(group.swap_remove(0));
+                }
+                true
+            }
+            CollisionPolicy::Rename => {
+                for (i, mut op) in group.into_iter().enumerate() {
+                    if i > 0 || exists {
+                        let path = renamed(&target, &taken, on_disk);
+                        op.target = path.to_string_lossy().to_string();
+                        taken.insert(path);
+                    }
+                    operations.push(op);
+                }
+                true
+            }
+            // 只覆盖已有的文件，多个文件导出到同一位置时无法覆盖
+            CollisionPolicy::Overwrite => {
+                let single = group.len() == 1;
+                for op in &mut group {
+                    op.overwrite = exists;
+                }
+                operations.extend(group);
+                single
+            }
+        };
+        collisions.push(ExportCollision {
+            target: target.to_string_lossy().to_string(),
+            sources,
+            exists,
+            resolved,
+        });
+    }
+
+    (operations, collisions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn operation(source: &str, target: &Path) -> ExportOperation {
+        ExportOperation {
+            kind: OperationKind::Copy,
+            filename: "case1.png".to_string(),
+            model: source.to_string(),
+            source: source.to_string(),
+            target: target.to_string_lossy().to_string(),
+            overwrite: false,
+            gt_path: None,
+            original_path: None,
+            predictions: Vec::new(),
+        }
+    }
+
+    fn values() -> HashMap<&'static str, String> {
+        let image = ExportImageInfo {
+            filename: "sub/case1.png".to_string(),
+            model_paths: HashMap::new(),
+            iou_scores: HashMap::from([("unet".to_string(), 0.87654)]),
+            accuracy_scores: HashMap::new(),
+            dice_scores: HashMap::new(),
+        };
+        template_values(&image, "a/b", "case1", ".png")
+    }
+
+    #[test]
+    fn render_template_fills_placeholders() {
+        let mut values = values();
+        values.insert("model", "unet".to_string());
+        values.insert("iou", "0.8765".to_string());
+        assert_eq!(render_template(DEFAULT_TEMPLATE, &values).unwrap(), "unet_case1.png");
+        assert_eq!(
+            render_template("{group}_{stem}_{iou}_{dice}{ext}", &values).unwrap(),
+            "sub_case1_0.8765_NA.png"
+        );
+    }
+
+    #[test]
+    fn render_template_sanitizes_model_names() {
+        assert_eq!(render_template("{model}{ext}", &values()).unwrap(), "a_b.png");
+    }
+
+    #[test]
+    fn render_template_rejects_paths() {
+        let values = values();
+        assert!(render_template("dir/{stem}{ext}", &values).is_err());
+        assert!(render_template("..", &values).is_err());
+        assert!(render_template("", &values).is_err());
+    }
+
+    #[test]
+    fn check_template_rejects_unknown_placeholders() {
+        assert!(check_template("{model}_{stem}{ext}").is_ok());
+        assert!(check_template("{name}{ext}").is_err());
+    }
+
+    #[test]
+    fn duplicate_targets_fail_by_default() {
+        let target = PathBuf::from("out/unet_case1.png");
+        let groups = vec![(target.clone(), vec![operation("a", &target), operation("b", &target)])];
+        let (operations, collisions) = resolve_collisions(groups, CollisionPolicy::Fail, false);
+        assert_eq!(operations.len(), 2);
+        assert_eq!(collisions.len(), 1);
+        assert!(!collisions[0].resolved);
+        assert_eq!(collisions[0].sources, ["a", "b"]);
+    }
+
+    #[test]
+    fn duplicate_targets_are_skipped_or_renamed() {
+        let target = PathBuf::from("out/unet_case1.png");
+        let group = || vec![(target.clone(), vec![operation("a", &target), operation("b", &target)])];
+
+        let (operations, collisions) = resolve_collisions(group(), CollisionPolicy::Skip, false);
+        assert_eq!(operations.len(), 1);
+        assert_eq!(operations[0].source, "a");
+        assert!(collisions[0].resolved);
+
+        let (operations, collisions) = resolve_collisions(group(), CollisionPolicy::Rename, false);
+        let targets: Vec<&str> = operations.iter().map(|op| op.target.as_str()).collect();
+        assert_eq!(targets, [target.to_str().unwrap(), Path::new("out/unet_case1_1.png").to_str().unwrap()]);
+        assert!(collisions[0].resolved);
+
+        // 多个文件导出到同一位置时无法覆盖
+        let (_, collisions) = resolve_collisions(group(), CollisionPolicy::Overwrite, false);
+        assert!(!collisions[0].resolved);
+    }
+
+    #[test]
+    fn rename_avoids_planned_targets() {
+        let target = PathBuf::from("out/x.png");
+        let taken_target = PathBuf::from("out/x_1.png");
+        let groups = vec![
+            (target.clone(), vec![operation("a", &target), operation("b", &target)]),
+            (taken_target.clone(), vec![operation("c", &taken_target)]),
+        ];
+        let (operations, _) = resolve_collisions(groups, CollisionPolicy::Rename, false);
+        let targets: HashSet<&str> = operations.iter().map(|op| op.target.as_str()).collect();
+        assert_eq!(targets.len(), 3);
+        assert!(targets.contains(Path::new("out/x_2.png").to_str().unwrap()));
+    }
+
+    #[test]
+    fn existing_files_follow_the_policy() {
+        let folder = std::env::temp_dir().join(format!("export_collision_{}", std::process::id()));
+        std::fs::create_dir_all(&folder).unwrap();
+        let target = folder.join("unet_case1.png");
+        std::fs::write(&target, b"old").unwrap();
+        let group = || vec![(target.clone(), vec![operation("a", &target)])];
+
+        let (operations, collisions) = resolve_collisions(group(), CollisionPolicy::Fail, true);
+        assert!(collisions[0].exists && !collisions[0].resolved);
+        assert!(operations[0].overwrite);
+
+        let (operations, _) = resolve_collisions(group(), CollisionPolicy::Skip, true);
+        assert!(operations.is_empty());
+
+        let (operations, _) = resolve_collisions(group(), CollisionPolicy::Rename, true);
+        assert_eq!(Path::new(&operations[0].target), folder.join("unet_case1_1.png"));
+
+        let (operations, collisions) = resolve_collisions(group(), CollisionPolicy::Overwrite, true);
+        assert!(collisions[0].resolved && operations[0].overwrite);
+
+        // 导出为压缩包时不考虑磁盘上已有的文件
+        let (_, collisions) = resolve_collisions(group(), CollisionPolicy::Fail, false);
+        assert!(collisions.is_empty());
+
+        std::fs::remove_dir_all(&folder).unwrap();
+    }
+
+    #[test]
+    fn sidecar_name_avoids_archive_entries() {
+        let folder = Path::new("out");
+        let plan = |targets: &[&str]| ExportPlan {
+            export_folder: "out".to_string(),
+            archive: Some("out/export.zip".to_string()),
+            operations: targets.iter().map(|t| operation("a", &folder.join(t))).collect(),
+            collisions: Vec::new(),
+            errors: Vec::new(),
+            sidecar_name: None,
+        };
+
+        assert_eq!(plan(&["case1.png"]).free_sidecar_name(), "metrics");
+        assert_eq!(plan(&["Metrics.CSV"]).free_sidecar_name(), "metrics_1");
+        assert_eq!(plan(&["metrics.json", "metrics_1.csv"]).free_sidecar_name(), "metrics_2");
+        // 子文件夹中的同名文件不冲突
+        assert_eq!(plan(&["unet/metrics.csv"]).free_sidecar_name(), "metrics");
+    }
+}
@@ -0,0 +1,509 @@
+// 论文用的定性对比图：每行一张图片，每列一个角色（输入、GT、我的方法与对照方法），
+// 可加列标题、预测格下方的指标图注、误差叠加或轮廓模式以及局部放大框，同一排版输出为 PNG 与 SVG
+
+use crate::contour::{ContourOptions, ContourPrediction, ContourStyle};
+use crate::export::CollisionPolicy;
+use crate::overlay::OverlayOptions;
+use crate::palette::Palette;
+use crate::report::escape_html;
+use crate::roles::{RoleKeys, RoleSet};
+use crate::{render, summary, text, ComparisonResult};
+use image::imageops::FilterType;
+use image::{DynamicImage, GenericImageView, RgbImage};
+use imageproc::drawing::draw_filled_rect_mut;
+use imageproc::rect::Rect;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// 预测格的显示方式：掩码本身、相对 GT 的误差叠加图，或画在原图上的轮廓
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CellMode {
+    #[default]
+    Plain,
+    Overlay,
+    Contour,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+}
+
+// 局部放大：在每格中框出同一区域，并把放大后的区域贴在格子的一角
+#[derive(Debug, Clone, Deserialize)]
+pub struct Inset {
+    // 放大区域，为相对图片宽高的比例（0 到 1）
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    // 放大图的宽度占格子宽度的比例
+    #[serde(default = "default_inset_size")]
+    pub size: f32,
+    #[serde(default)]
+    pub corner: Corner,
+    #[serde(default = "default_inset_color")]
+    pub color: [u8; 3],
+    #[serde(default = "default_line_width")]
+    pub line_width: u32,
+}
+
+fn default_inset_size() -> f32 {
+    0.4
+}
+
+fn default_inset_color() -> [u8; 3] {
+    [255, 200, 0]
+}
+
+fn default_line_width() -> u32 {
+    2
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FigureRequest {
+    // 每行一张图片，按给出的顺序
+    pub results: Vec<ComparisonResult>,
+    // 列的顺序，为 paths 中的键
+    pub columns: Vec<String>,
+    pub export_folder: String,
+    // 文件名（不含扩展名），默认 figure
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub on_collision: CollisionPolicy,
+    #[serde(default = "default_headers")]
+    pub headers: bool,
+    // 列标题默认为列的键，可按键改名；roles 中标记为我的方法的列默认标题后加 (Ours)
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+    #[serde(default)]
+    pub roles: Option<RoleSet>,
+    // 在预测格下方标注该图片的指标
+    #[serde(default)]
+    pub caption_metric: Option<String>,
+    #[serde(default = "default_decimals")]
+    pub decimals: usize,
+    #[serde(default)]
+    pub mode: CellMode,
+    #[serde(default)]
+    pub inset: Option<Inset>,
+    // 每格的宽度（像素），高度按该行第一格图片的比例
+    #[serde(default = "default_cell_size")]
+    pub cell_size: u32,
+    #[serde(default = "default_spacing")]
+    pub spacing: u32,
+    #[serde(default = "default_font_size")]
+    pub font_size: f32,
+    // paths 中 GT 与原始图片对应的键，默认取自 roles；原始图片的键为空时没有原图
+    #[serde(default)]
+    pub gt_key: Option<String>,
+    #[serde(default)]
+    pub original_key: Option<String>,
+    #[serde(default)]
+    pub palette: Option<Palette>,
+    #[serde(default)]
+    pub overlay: OverlayOptions,
+    #[serde(default)]
+    pub contour: ContourOptions,
+}
+
+fn default_headers() -> bool {
+    true
+}
+
+fn default_decimals() -> usize {
+    3
+}
+
+fn default_cell_size() -> u32 {
+    512
+}
+
+fn default_spacing() -> u32 {
+    8
+}
+
+fn default_font_size() -> f32 {
+    28.0
+}
+
+// 单格宽度、间距与字号的上限，以及整张图的尺寸上限，避免坐标溢出或分配过大的画布
+const MAX_CELL_SIZE: u32 = 4096;
+const MAX_SPACING: u32 = 256;
+const MAX_FONT_SIZE: f32 = 256.0;
+const MAX_CANVAS_SIDE: u64 = 32768;
+const MAX_CANVAS_PIXELS: u64 = 200_000_000;
+
+fn check_canvas(width: u64, height: u64) -> Result<(), String> {
+    if width > MAX_CANVAS_SIDE || height > MAX_CANVAS_SIDE || width * height > MAX_CANVAS_PIXELS {
+        return Err(format!(
+            "对比图尺寸过大（{}x{}），请减少图片或列数，或减小 cell_size",
+            width, height
+        ));
+    }
+    Ok(())
+}
+
+// PNG 与 SVG 共用的排版，坐标以像素计
+enum Item {
+    Image { x: u32, y: u32, image: RgbImage },
+    // 线条画在矩形之内
+    Frame { x: u32, y: u32, width: u32, height: u32, color: [u8; 3], line_width: u32 },
+    Text { center_x: u32, top: u32, size: f32, text: String },
+}
+
+pub struct Layout {
+    width: u32,
+    height: u32,
+    items: Vec<Item>,
+}
+
+// 按显示方式渲染某张图片某个角色的格子，对比图与闪烁动画共用
+pub struct Cells<'a> {
+    pub mode: CellMode,
+    // 参与显示的全部角色，轮廓模式按其中预测的顺序分配颜色
+    pub columns: &'a [String],
+    pub gt_key: &'a str,
+    pub original_key: Option<&'a str>,
+    pub palette: Option<&'a Palette>,
+    pub overlay: &'a OverlayOptions,
+    pub contour: &'a ContourOptions,
+}
+
+impl Cells<'_> {
+    pub fn is_original(&self, column: &str) -> bool {
+        self.original_key == Some(column)
+    }
+
+    pub fn image(&self, result: &ComparisonResult, column: &str) -> Result<DynamicImage, String> {
+        let path = |key: &str| result.paths.get(key).map(String::as_str);
+        let source = path(column).ok_or_else(|| format!("{} 没有 {} 的文件", result.filename, column))?;
+        let original = self.original_key.and_then(path);
+        let filename = &result.filename;
+
+        let image = if self.is_original(column) {
+            render::load_display_image(source)
+        } else if column == self.gt_key {
+            match self.mode {
+                CellMode::Contour => self.contour_cell(result, None, source, original),
+                _ => crate::load_reference_mask(source, filename, original, self.palette)
+                    .map(|m| render::mask_to_image(&m)),
+            }
+        } else {
+            match (self.mode, path(self.gt_key)) {
+                (CellMode::Plain, _) => crate::load_role_mask(source, self.palette).map(|m| render::mask_to_image(&m)),
+                (CellMode::Overlay, Some(gt)) => {
+                    crate::render_error_overlay(filename, gt, source, original, self.palette, self.overlay)
+                }
+                (CellMode::Contour, Some(gt)) => self.contour_cell(result, Some(column), gt, original),
+                (_, None) => Err("没有 GT，无法生成叠加图".to_string()),
+            }
+        };
+        image.map_err(|e| format!("{} 的 {} 生成失败: {}", filename, column, e))
+    }
+
+    // 轮廓模式下每格只画 GT 与该列的模型；其余预测列隐藏但保留位置，颜色按预测列的顺序分配
+    fn contour_cell(
+        &self,
+        result: &ComparisonResult,
+        column: Option<&str>,
+        gt_path: &str,
+        original: Option<&str>,
+    ) -> Result<DynamicImage, String> {
+        let predictions: Vec<ContourPrediction> = self
+            .columns
+            .iter()
+            .filter(|c| !self.is_original(c) && *c != self.gt_key)
+            .map(|c| ContourPrediction {
+                model: c.clone(),
+                path: result.paths.get(c).cloned().unwrap_or_default(),
+            })
+            .collect();
+        let mut options = self.contour.clone();
+        options.show_gt |= column.is_none();
+        for prediction in predictions.iter().filter(|p| Some(p.model.as_str()) != column) {
+            options
+                .models
+                .entry(prediction.model.clone())
+                .and_modify(|style| style.visible = false)
+                .or_insert(ContourStyle {
+                    color: None,
+                    width: None,
+                    visible: false,
+                });
+        }
+        crate::render_contour_overlay(&result.filename, gt_path, &predictions, original, self.palette, &options)
+    }
+}
+
+impl FigureRequest {
+    fn keys(&self) -> Result<RoleKeys, String> {
+        RoleKeys::resolve(self.gt_key.as_deref(), self.original_key.as_deref(), self.roles.as_ref())
+    }
+
+    fn cells<'a>(&'a self, keys: &'a RoleKeys) -> Cells<'a> {
+        Cells {
+            mode: self.mode,
+            columns: &self.columns,
+            gt_key: &keys.gt,
+            original_key: keys.original.as_deref(),
+            palette: self.palette.as_ref(),
+            overlay: &self.overlay,
+            contour: &self.contour,
+        }
+    }
+
+    fn header(&self, column: &str) -> String {
+        if let Some(label) = self.labels.get(column) {
+            return label.clone();
+        }
+        match &self.roles {
+            Some(roles) if roles.ours().iter().any(|id| id == column) => format!("{} (Ours)", column),
+            _ => column.to_string(),
+        }
+    }
+
+    fn caption(&self, keys: &RoleKeys, result: &ComparisonResult, column: &str) -> Option<String> {
+        let metric = self.caption_metric.as_deref()?;
+        if keys.original.as_deref() == Some(column) || column == keys.gt {
+            return None;
+        }
+        let value = summary::scores(result, metric)?.get(column)?;
+        Some(format!("{} {:.*}", summary::metric_title(metric), self.decimals, value))
+    }
+}
+
+// 缩放到格子尺寸：原始图片与缩小时平滑插值，放大掩码类图片时保持像素清晰
+pub fn fit(image: &DynamicImage, width: u32, height: u32, smooth: bool) -> RgbImage {
+    let filter = if smooth || width < image.width() {
+        FilterType::Triangle
+    } else {
+        FilterType::Nearest
+    };
+    image.resize_exact(width, height, filter).to_rgb8()
+}
+
+// 在格子 (x, y, width, height) 中框出放大区域，并把放大图贴在指定的角上
+fn inset_items(inset: &Inset, source: &DynamicImage, x: u32, y: u32, width: u32, height: u32) -> Vec<Item> {
+    let (source_width, source_height) = source.dimensions();
+    let region = |start: f32, length: f32, total: u32| {
+        let start = ((start.clamp(0.0, 1.0) * total as f32).round() as u32).min(total - 1);
+        let length = ((length.clamp(0.0, 1.0) * total as f32).round() as u32).clamp(1, total - start);
+        (start, length)
+    };
+    let (region_x, region_width) = region(inset.x, inset.width, source_width);
+    let (region_y, region_height) = region(inset.y, inset.height, source_height);
+    let line_width = inset.line_width.max(1);
+
+    let zoom_width = ((width as f32 * inset.size.clamp(0.05, 1.0)).round() as u32).clamp(1, width);
+    let zoom_height = (zoom_width as u64 * region_height as u64 / region_width as u64).clamp(1, height as u64) as u32;
+    let zoomed = source
+        .crop_imm(region_x, region_y, region_width, region_height)
+        .resize_exact(zoom_width, zoom_height, FilterType::Nearest)
+        .to_rgb8();
+    let (zoom_x, zoom_y) = match inset.corner {
+        Corner::TopLeft => (x, y),
+        Corner::TopRight => (x + width - zoom_width, y),
+        Corner::BottomLeft => (x, y + height - zoom_height),
+        Corner::BottomRight => (x + width - zoom_width, y + height - zoom_height),
+    };
+
+    let scale_x = |v: u32| (v as u64 * width as u64 / source_width as u64) as u32;
+    let scale_y = |v: u32| (v as u64 * height as u64 / source_height as u64) as u32;
+    vec![
+        Item::Frame {
+            x: x + scale_x(region_x),
+            y: y + scale_y(region_y),
+            width: scale_x(region_width).max(line_width * 2),
+            height: scale_y(region_height).max(line_width * 2),
+            color: inset.color,
+            line_width,
+        },
+        Item::Image {
+            x: zoom_x,
+            y: zoom_y,
+            image: zoomed,
+        },
+        Item::Frame {
+            x: zoom_x,
+            y: zoom_y,
+            width: zoom_width,
+            height: zoom_height,
+            color: inset.color,
+            line_width,
+        },
+    ]
+}
+
+pub fn compose(request: &FigureRequest) -> Result<Layout, String> {
+    if request.results.is_empty() || request.columns.is_empty() {
+        return Err("请至少选择一张图片和一列".to_string());
+    }
+    if let Some(roles) = &request.roles {
+        roles.require_ours()?;
+    }
+    if let Some(metric) = request.caption_metric.as_deref() {
+        if !summary::METRICS.contains(&metric) {
+            return Err(format!("未知的指标: {}，可用: {}", metric, summary::METRICS.join(", ")));
+        }
+    }
+
+    let spacing = request.spacing.min(MAX_SPACING);
+    let cell_width = request.cell_size.clamp(16, MAX_CELL_SIZE);
+    let font_size = request.font_size.clamp(1.0, MAX_FONT_SIZE);
+    let caption_size = font_size * 0.8;
+    let header_height = if request.headers { (font_size * 1.5).ceil() as u32 } else { 0 };
+    let caption_height = if request.caption_metric.is_some() {
+        (caption_size * 1.5).ceil() as u32
+    } else {
+        0
+    };
+    // 宽度在排版之前确定，检查通过后各列的坐标不会超出 u32
+    let width = spacing as u64 + request.columns.len() as u64 * (cell_width + spacing) as u64;
+    check_canvas(width, 1)?;
+    let column_x = |column: usize| spacing + column as u32 * (cell_width + spacing);
+
+    let mut items = Vec::new();
+    let mut y = spacing;
+    if request.headers {
+        for (column, key) in request.columns.iter().enumerate() {
+            items.push(Item::Text {
+                center_x: column_x(column) + cell_width / 2,
+                top: y,
+                size: font_size,
+                text: request.header(key),
+            });
+        }
+        y += header_height;
+    }
+
+    let keys = request.keys()?;
+    let cells = request.cells(&keys);
+    for result in &request.results {
+        let images = request
+            .columns
+            .iter()
+            .map(|column| cells.image(result, column))
+            .collect::<Result<Vec<_>, String>>()?;
+        let (first_width, first_height) = images[0].dimensions();
+        let row_height = ((cell_width as u64 * first_height as u64) / first_width.max(1) as u64).max(1);
+        // 在缩放图片之前检查加上这一行之后的画布尺寸
+        check_canvas(width, y as u64 + row_height + caption_height as u64 + spacing as u64)?;
+        let row_height = row_height as u32;
+
+        for (column, (key, image)) in request.columns.iter().zip(&images).enumerate() {
+            let x = column_x(column);
+            items.push(Item::Image {
+                x,
+                y,
+                image: fit(image, cell_width, row_height, cells.is_original(key)),
+            });
+            if let Some(inset) = &request.inset {
+                items.extend(inset_items(inset, image, x, y, cell_width, row_height));
+            }
+            if let Some(caption) = request.caption(&keys, result, key) {
+                items.push(Item::Text {
+                    center_x: x + cell_width / 2,
+                    top: y + row_height + (caption_size * 0.25) as u32,
+                    size: caption_size,
+                    text: caption,
+                });
+            }
+        }
+        y += row_height + caption_height + spacing;
+    }
+
+    Ok(Layout {
+        width: column_x(request.columns.len()),
+        height: y,
+        items,
+    })
+}
+
+pub fn png(layout: &Layout) -> Result<Vec<u8>, String> {
+    let mut canvas = RgbImage::from_pixel(layout.width, layout.height, image::Rgb([255, 255, 255]));
+    let has_text = layout.items.iter().any(|item| matches!(item, Item::Text { .. }));
+    let font = if has_text { Some(text::font()?) } else { None };
+
+    for item in &layout.items {
+        match item {
+            Item::Image { x, y, image } => image::imageops::replace(&mut canvas, image, *x as i64, *y as i64),
+            Item::Frame { x, y, width, height, color, line_width } => {
+                let (x, y, line) = (*x as i32, *y as i32, (*line_width).min(*width).min(*height));
+                let sides = [
+                    Rect::at(x, y).of_size(*width, line),
+                    Rect::at(x, y + (*height - line) as i32).of_size(*width, line),
+                    Rect::at(x, y).of_size(line, *height),
+                    Rect::at(x + (*width - line) as i32, y).of_size(line, *height),
+                ];
+                for side in sides {
+                    draw_filled_rect_mut(&mut canvas, side, image::Rgb(*color));
+                }
+            }
+            Item::Text { center_x, top, size, text } => {
+                if let Some(font) = font {
+                    text::draw_centered(&mut canvas, font, [0, 0, 0], *center_x as i32, *top as i32, *size, text);
+                }
+            }
+        }
+    }
+    render::png_bytes(&DynamicImage::ImageRgb8(canvas))
+}
+
+// 图片以 PNG 数据 URL 内嵌，文字保留为可编辑的文本
+pub fn svg(layout: &Layout) -> Result<String, String> {
+    let mut svg = format!(
+        concat!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" "#,
+            r#"width="{0}" height="{1}" viewBox="0 0 {0} {1}">"#,
+            "\n",
+            r#"<rect width="{0}" height="{1}" fill="white"/>"#,
+            "\n"
+        ),
+        layout.width, layout.height
+    );
+    for item in &layout.items {
+        let element = match item {
+            Item::Image { x, y, image } => format!(
+                r#"<image x="{}" y="{}" width="{}" height="{}" xlink:href="{}"/>"#,
+                x,
+                y,
+                image.width(),
+                image.height(),
+                render::png_data_url(&DynamicImage::ImageRgb8(image.clone()))?
+            ),
+            Item::Frame { x, y, width, height, color, line_width } => {
+                let half = *line_width as f32 / 2.0;
+                format!(
+                    r#"<rect x="{}" y="{}" width="{}" height="{}" fill="none" stroke="rgb({},{},{})" stroke-width="{}"/>"#,
+                    *x as f32 + half,
+                    *y as f32 + half,
+                    (*width as f32 - *line_width as f32).max(0.0),
+                    (*height as f32 - *line_width as f32).max(0.0),
+                    color[0],
+                    color[1],
+                    color[2],
+                    line_width
+                )
+            }
+            Item::Text { center_x, top, size, text } => format!(
+                r#"<text x="{}" y="{}" font-size="{}" text-anchor="middle" font-family="Arial, 'Microsoft YaHei', 'PingFang SC', sans-serif">{}</text>"#,
+                center_x,
+                *top as f32 + size * 0.8,
+                size,
+                escape_html(text)
+            ),
+        };
+        svg.push_str(&element);
+        svg.push('\n');
+    }
+    svg.push_str("</svg>\n");
+    Ok(svg)
+}
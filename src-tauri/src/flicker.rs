@@ -0,0 +1,191 @@
+// 闪烁对比动画：同一张图片的两个或多个角色按固定间隔轮流显示，写为循环播放的 GIF 或 APNG，
+// 每帧左上角标出角色名称，便于看出边界上的细微差别
+
+use crate::contour::ContourOptions;
+use crate::export::CollisionPolicy;
+use crate::figure::{self, CellMode, Cells};
+use crate::overlay::OverlayOptions;
+use crate::palette::Palette;
+use crate::roles::{RoleKeys, RoleSet};
+use crate::{matching, text, ComparisonResult};
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, DynamicImage, Frame, RgbImage};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnimationFormat {
+    #[default]
+    Gif,
+    Apng,
+}
+
+impl AnimationFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            AnimationFormat::Gif => ".gif",
+            AnimationFormat::Apng => ".png",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FlickerRequest {
+    pub result: ComparisonResult,
+    // 轮流显示的角色，为 paths 中的键，至少两个
+    pub roles: Vec<String>,
+    pub export_folder: String,
+    // 文件名（不含扩展名），默认为 {图片名}_flicker
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub on_collision: CollisionPolicy,
+    #[serde(default)]
+    pub format: AnimationFormat,
+    // 每帧显示的毫秒数
+    #[serde(default = "default_frame_duration")]
+    pub frame_duration: u32,
+    // 显示掩码本身、误差叠加图或轮廓
+    #[serde(default)]
+    pub mode: CellMode,
+    #[serde(default = "default_show_labels")]
+    pub show_labels: bool,
+    // 标签默认为角色的键，可按键改名
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+    #[serde(default = "default_font_size")]
+    pub font_size: f32,
+    // 长边超过该像素数时缩小，0 为不限制
+    #[serde(default)]
+    pub max_size: u32,
+    // paths 中 GT 与原始图片对应的键，默认取自 roles；原始图片的键为空时没有原图
+    #[serde(default)]
+    pub gt_key: Option<String>,
+    #[serde(default)]
+    pub original_key: Option<String>,
+    // 角色配置（roles 已用于轮流显示的角色）
+    #[serde(default)]
+    pub role_set: Option<RoleSet>,
+    #[serde(default)]
+    pub palette: Option<Palette>,
+    #[serde(default)]
+    pub overlay: OverlayOptions,
+    #[serde(default)]
+    pub contour: ContourOptions,
+}
+
+fn default_frame_duration() -> u32 {
+    500
+}
+
+fn default_show_labels() -> bool {
+    true
+}
+
+fn default_font_size() -> f32 {
+    20.0
+}
+
+impl FlickerRequest {
+    pub fn file_name(&self) -> String {
+        let name = match &self.name {
+            Some(name) => name.clone(),
+            None => {
+                let name = self.result.filename.rsplit('/').next().unwrap_or(&self.result.filename);
+                format!("{}_flicker", matching::split_extension(name).0)
+            }
+        };
+        format!("{}{}", name, self.format.extension())
+    }
+}
+
+// 各帧统一为第一帧的尺寸，再按 max_size 等比缩小，并画上角色标签
+fn frames(request: &FlickerRequest) -> Result<Vec<RgbImage>, String> {
+    if request.roles.len() < 2 {
+        return Err("请至少选择两个角色".to_string());
+    }
+    let keys = RoleKeys::resolve(
+        request.gt_key.as_deref(),
+        request.original_key.as_deref(),
+        request.role_set.as_ref(),
+    )?;
+    let cells = Cells {
+        mode: request.mode,
+        columns: &request.roles,
+        gt_key: &keys.gt,
+        original_key: keys.original.as_deref(),
+        palette: request.palette.as_ref(),
+        overlay: &request.overlay,
+        contour: &request.contour,
+    };
+    let images = request
+        .roles
+        .iter()
+        .map(|role| cells.image(&request.result, role))
+        .collect::<Result<Vec<DynamicImage>, String>>()?;
+
+    let (mut width, mut height) = (images[0].width(), images[0].height());
+    let longest = width.max(height);
+    if request.max_size > 0 && longest > request.max_size {
+        width = (width as u64 * request.max_size as u64 / longest as u64).max(1) as u32;
+        height = (height as u64 * request.max_size as u64 / longest as u64).max(1) as u32;
+    }
+    let font = if request.show_labels { Some(text::font()?) } else { None };
+
+    let mut frames = Vec::new();
+    for (role, image) in request.roles.iter().zip(&images) {
+        let mut frame = figure::fit(image, width, height, cells.is_original(role));
+        if let Some(font) = font {
+            let label = request.labels.get(role).unwrap_or(role);
+            text::draw_label(&mut frame, font, request.font_size.max(1.0), label);
+        }
+        frames.push(frame);
+    }
+    Ok(frames)
+}
+
+fn gif(frames: Vec<RgbImage>, duration: u32) -> Result<Vec<u8>, String> {
+    let failed = |e: image::ImageError| format!("GIF 编码失败: {}", e);
+    let mut data = Vec::new();
+    {
+        let mut encoder = GifEncoder::new_with_speed(&mut data, 10);
+        encoder.set_repeat(Repeat::Infinite).map_err(failed)?;
+        let frames = frames.into_iter().map(|frame| {
+            let rgba = DynamicImage::ImageRgb8(frame).to_rgba8();
+            Frame::from_parts(rgba, 0, 0, Delay::from_numer_denom_ms(duration, 1))
+        });
+        encoder.encode_frames(frames).map_err(failed)?;
+    }
+    Ok(data)
+}
+
+fn apng(frames: Vec<RgbImage>, duration: u32) -> Result<Vec<u8>, String> {
+    let failed = |e: png::EncodingError| format!("APNG 编码失败: {}", e);
+    let (width, height) = frames[0].dimensions();
+    let mut data = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut data, width, height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        // 播放次数为 0 表示无限循环
+        encoder.set_animated(frames.len() as u32, 0).map_err(failed)?;
+        encoder.set_frame_delay(duration.min(u16::MAX as u32) as u16, 1000).map_err(failed)?;
+        let mut writer = encoder.write_header().map_err(failed)?;
+        for frame in &frames {
+            writer.write_image_data(frame.as_raw()).map_err(failed)?;
+        }
+        writer.finish().map_err(failed)?;
+    }
+    Ok(data)
+}
+
+pub fn render(request: &FlickerRequest) -> Result<Vec<u8>, String> {
+    // GIF 的帧间隔以 10 毫秒为单位，过短时多数查看器会改为默认速度
+    let duration = request.frame_duration.clamp(20, 60_000);
+    let frames = frames(request)?;
+    match request.format {
+        AnimationFormat::Gif => gif(frames, duration),
+        AnimationFormat::Apng => apng(frames, duration),
+    }
+}
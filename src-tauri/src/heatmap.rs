@@ -0,0 +1,141 @@
+// 数据集级误差热力图与逐图片的模型共识图：统计每个像素在所有文件上的 FP/FN 频率，以及每个像素有多少个模型预测为前景
+
+use crate::mask::MaskData;
+use crate::render;
+use serde::{Deserialize, Serialize};
+
+// 热力图默认的统一网格尺寸
+pub const DEFAULT_GRID_SIZE: [u32; 2] = [256, 256];
+
+// 网格每边的上限，避免来自前端的尺寸导致溢出或分配过大的内存
+pub const MAX_GRID_SIDE: u32 = 4096;
+
+// 单个模型的 FP/FN 累计，所有文件按最近邻采样到统一网格
+pub struct ErrorAccumulator {
+    width: u32,
+    height: u32,
+    false_positive: Vec<u32>,
+    false_negative: Vec<u32>,
+    files: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelHeatmap {
+    pub model: String,
+    pub files: u32,
+    pub width: u32,
+    pub height: u32,
+    // 网格中 FP/FN 频率的最大值，热力图按 0 到 1 的绝对频率着色，便于模型之间比较
+    pub max_fp_rate: f32,
+    pub max_fn_rate: f32,
+    pub fp_image: String,
+    pub fn_image: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsensusMap {
+    pub models: usize,
+    pub width: u32,
+    pub height: u32,
+    // 颜色从黑（没有模型预测为前景）到白（所有模型都预测为前景）
+    pub image: String,
+}
+
+impl ErrorAccumulator {
+    pub fn new(grid_size: [u32; 2]) -> Result<Self, String> {
+        let [width, height] = grid_size;
+        if !(1..=MAX_GRID_SIDE).contains(&width) || !(1..=MAX_GRID_SIDE).contains(&height) {
+            return Err(format!(
+                "热力图网格尺寸无效: {}x{}，每边应在 1 到 {} 之间",
+                width, height, MAX_GRID_SIDE
+            ));
+        }
+        let len = width as usize * height as usize;
+        Ok(ErrorAccumulator {
+            width,
+            height,
+            false_positive: vec![0; len],
+            false_negative: vec![0; len],
+            files: 0,
+        })
+    }
+
+    // GT 与预测需尺寸一致
+    pub fn add(&mut self, gt: &MaskData, pred: &MaskData) {
+        for gy in 0..self.height {
+            let y = (gy as u64 * gt.height as u64 / self.height as u64) as u32;
+            for gx in 0..self.width {
+                let x = (gx as u64 * gt.width as u64 / self.width as u64) as u32;
+                let index = gy as usize * self.width as usize + gx as usize;
+                match (gt.is_foreground(x, y), pred.is_foreground(x, y)) {
+                    (false, true) => self.false_positive[index] += 1,
+                    (true, false) => self.false_negative[index] += 1,
+                    _ => {}
+                }
+            }
+        }
+        self.files += 1;
+    }
+
+    // 生成 FP、FN 频率热力图，同时返回两张图片供导出保存
+    pub fn finish(&self, model: &str) -> Result<(ModelHeatmap, [image::DynamicImage; 2]), String> {
+        let rates = |counts: &[u32]| -> Vec<f32> {
+            counts.iter().map(|&c| c as f32 / self.files.max(1) as f32).collect()
+        };
+        let (fp_rates, fn_rates) = (rates(&self.false_positive), rates(&self.false_negative));
+        let fp_image = render::heatmap_image(&fp_rates, self.width, self.height);
+        let fn_image = render::heatmap_image(&fn_rates, self.width, self.height);
+
+        let heatmap = ModelHeatmap {
+            model: model.to_string(),
+            files: self.files,
+            width: self.width,
+            height: self.height,
+            max_fp_rate: fp_rates.iter().copied().fold(0.0, f32::max),
+            max_fn_rate: fn_rates.iter().copied().fold(0.0, f32::max),
+            fp_image: render::png_data_url(&fp_image)?,
+            fn_image: render::png_data_url(&fn_image)?,
+        };
+        Ok((heatmap, [fp_image, fn_image]))
+    }
+}
+
+// 每个像素预测为前景的模型数量占比；预测缩放到第一个模型的尺寸
+pub fn consensus_image(predictions: &[MaskData]) -> Option<image::DynamicImage> {
+    let first = predictions.first()?;
+    let (width, height) = (first.width, first.height);
+
+    let mut counts = vec![0u32; width as usize * height as usize];
+    for prediction in predictions {
+        let resized;
+        let prediction = if prediction.width != width || prediction.height != height {
+            resized = prediction.clone().resize(width, height);
+            &resized
+        } else {
+            prediction
+        };
+        for y in 0..height {
+            for x in 0..width {
+                if prediction.is_foreground(x, y) {
+                    counts[y as usize * width as usize + x as usize] += 1;
+                }
+            }
+        }
+    }
+
+    let fractions: Vec<f32> = counts.iter().map(|&c| c as f32 / predictions.len() as f32).collect();
+    Some(render::heatmap_image(&fractions, width, height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid_size_is_bounded() {
+        assert!(ErrorAccumulator::new([0, 256]).is_err());
+        assert!(ErrorAccumulator::new([u32::MAX, u32::MAX]).is_err());
+        assert!(ErrorAccumulator::new([MAX_GRID_SIDE + 1, 1]).is_err());
+        assert!(ErrorAccumulator::new(DEFAULT_GRID_SIZE).is_ok());
+    }
+}
@@ -1,11 +1,36 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use image::GenericImageView;
+mod agreement;
+mod archive;
+mod annotations;
+mod consensus;
+mod contour;
+mod export;
+mod figure;
+mod flicker;
+mod heatmap;
+mod mask;
+mod matching;
+mod overlay;
+mod palette;
+mod probe;
+mod preflight;
+mod registry;
+mod render;
+mod report;
+mod results_table;
+mod roles;
+mod scan;
+mod spreadsheet;
+mod summary;
+mod text;
+mod thumbnails;
+mod volume;
+
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::fs;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
-use tauri::Emitter;
+use tauri::{Emitter, Manager};
 
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -18,9 +43,20 @@ struct FolderInfo {
 #[derive(Debug, Serialize, Deserialize)]
 struct ComparisonResult {
     filename: String,
+    // 递归扫描时文件所在的子文件夹，用于分组，顶层文件为空字符串
+    group: String,
     iou_scores: HashMap<String, f64>,
     accuracy_scores: HashMap<String, f64>,
     dice_scores: HashMap<String, f64>,
+    // 以下仅对 NIfTI 体数据计算
+    hd95_scores: HashMap<String, f64>,
+    assd_scores: HashMap<String, f64>,
+    slice_scores: HashMap<String, Vec<volume::SliceScore>>,
+    // 仅在提供调色板时按类别计算
+    class_scores: HashMap<String, Vec<palette::ClassScore>>,
+    // 仅在有多个 GT 来源时计算：标注者之间的一致性，以及每个方法相对各标注者与共识 GT 的指标
+    annotator_agreement: Option<consensus::AnnotatorAgreement>,
+    reference_scores: HashMap<String, consensus::MethodReferenceScores>,
     paths: HashMap<String, String>,
 }
 
@@ -28,7 +64,13 @@ struct ComparisonResult {
 struct ValidationResult {
     is_valid: bool,
     common_files: Vec<String>,
+    // 按文件夹角色（原始图片、GT、我的实验数据、对照实验 N）记录缺失与多余的文件
     missing_files: HashMap<String, Vec<String>>,
+    extra_files: HashMap<String, Vec<String>>,
+    // 每个文件夹被隐藏文件、包含与排除过滤去除的文件数量
+    filter_stats: HashMap<String, scan::FilterStats>,
+    // 被数据划分文件去除的文件数量
+    split_removed: usize,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -41,79 +83,55 @@ struct ProgressEvent {
 
 
 
-// 获取文件夹中的所有图片文件
-fn get_image_files(dir_path: &str) -> Result<Vec<String>, String> {
+// 列出可比较的文件；GT 为标注文件或标注文件夹时返回标注中记录的图片文件名
+// 同时返回各个过滤条件去除的文件数量
+fn list_comparable_files(
+    path: &str,
+    options: &scan::ScanOptions,
+) -> Result<(Vec<String>, scan::FilterStats), String> {
+    let files = match annotations::AnnotationSet::open(path)? {
+        Some(annotations) => annotations.file_names(),
+        None => get_image_files(path, options)?,
+    };
+    options.apply(files)
+}
+
+// 获取文件夹中的所有图片文件，递归模式下返回相对路径
+fn get_image_files(dir_path: &str, options: &scan::ScanOptions) -> Result<Vec<String>, String> {
     let path = Path::new(dir_path);
     if !path.exists() {
         return Err(format!("文件夹不存在: {}", dir_path));
     }
 
-    let mut files = Vec::new();
-    let valid_extensions = vec!["jpg", "jpeg", "png", "bmp", "tiff", "webp"];
-
-    if let Ok(entries) = fs::read_dir(path) {
-        for entry in entries {
-            if let Ok(entry) = entry {
-                let path = entry.path();
-                if path.is_file() {
-                    if let Some(extension) = path.extension() {
-                        if let Some(ext_str) = extension.to_str() {
-                            if valid_extensions.contains(&ext_str.to_lowercase().as_str()) {
-                                if let Some(filename) = path.file_name() {
-                                    if let Some(name) = filename.to_str() {
-                                        files.push(name.to_string());
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
-    }
+    let mut files: Vec<String> = scan::walk(path, options)
+        .into_iter()
+        .filter(|relative| {
+            let name = relative.rsplit('/').next().unwrap_or(relative);
+            let extension = Path::new(name)
+                .extension()
+                .and_then(|e| e.to_str())
+                .map(|e| e.to_lowercase())
+                .unwrap_or_default();
+            mask::MASK_EXTENSIONS.contains(&extension.as_str()) || volume::is_volume_file(name)
+        })
+        .collect();
 
     files.sort();
     Ok(files)
 }
 
-// 计算两个图片的IOU
-fn calculate_iou(img1_path: &str, img2_path: &str, _window: Option<&tauri::Window>) -> Result<f64, String> {
-    let img1 = image::open(img1_path).map_err(|e| format!("无法打开图片1: {}", e))?;
-    let img2 = image::open(img2_path).map_err(|e| format!("无法打开图片2: {}", e))?;
-
-    // 这里简化IOU计算，实际应用中需要根据您的具体需求进行调整
+// 计算两个掩码的IOU，两者尺寸需一致
+fn calculate_iou(mask1: &mask::MaskData, mask2: &mask::MaskData) -> f64 {
     // 假设图片是分割掩码，计算像素级IOU
-    let (width1, height1) = img1.dimensions();
-    let (width2, height2) = img2.dimensions();
-
-    // 处理图像尺寸不一致的情况：将大的图像压缩到小的图像尺寸
-    let (target_width, target_height) = (
-        width1.min(width2),
-        height1.min(height2)
-    );
-
-    let img1_processed = if width1 != target_width || height1 != target_height {
-        img1.resize(target_width, target_height, image::imageops::FilterType::Lanczos3)
-    } else {
-        img1
-    };
-
-    let img2_processed = if width2 != target_width || height2 != target_height {
-        img2.resize(target_width, target_height, image::imageops::FilterType::Lanczos3)
-    } else {
-        img2
-    };
-
-    let img1_gray = img1_processed.to_luma8();
-    let img2_gray = img2_processed.to_luma8();
+    let (target_width, target_height) = (mask1.width, mask1.height);
 
     let mut intersection = 0u32;
     let mut union = 0u32;
 
     for y in 0..target_height {
         for x in 0..target_width {
-            let pixel1 = img1_gray.get_pixel(x, y)[0] > 128;
-            let pixel2 = img2_gray.get_pixel(x, y)[0] > 128;
+            let pixel1 = mask1.is_foreground(x, y);
+            let pixel2 = mask2.is_foreground(x, y);
 
             if pixel1 && pixel2 {
                 intersection += 1;
@@ -125,48 +143,23 @@ fn calculate_iou(img1_path: &str, img2_path: &str, _window: Option<&tauri::Windo
     }
 
     if union == 0 {
-        Ok(1.0) // 如果两个图片都是空的，IOU为1
+        1.0 // 如果两个图片都是空的，IOU为1
     } else {
-        Ok(intersection as f64 / union as f64)
+        intersection as f64 / union as f64
     }
 }
 
-// 计算两个图片的准确率
-fn calculate_accuracy(img1_path: &str, img2_path: &str, _window: Option<&tauri::Window>) -> Result<f64, String> {
-    let img1 = image::open(img1_path).map_err(|e| format!("无法打开图片1: {}", e))?;
-    let img2 = image::open(img2_path).map_err(|e| format!("无法打开图片2: {}", e))?;
-
-    let (width1, height1) = img1.dimensions();
-    let (width2, height2) = img2.dimensions();
-
-    // 处理图像尺寸不一致的情况：将大的图像压缩到小的图像尺寸
-    let (target_width, target_height) = (
-        width1.min(width2),
-        height1.min(height2)
-    );
-
-    let img1_processed = if width1 != target_width || height1 != target_height {
-        img1.resize(target_width, target_height, image::imageops::FilterType::Lanczos3)
-    } else {
-        img1
-    };
-
-    let img2_processed = if width2 != target_width || height2 != target_height {
-        img2.resize(target_width, target_height, image::imageops::FilterType::Lanczos3)
-    } else {
-        img2
-    };
-
-    let img1_gray = img1_processed.to_luma8();
-    let img2_gray = img2_processed.to_luma8();
+// 计算两个掩码的准确率，两者尺寸需一致
+fn calculate_accuracy(mask1: &mask::MaskData, mask2: &mask::MaskData) -> f64 {
+    let (target_width, target_height) = (mask1.width, mask1.height);
 
     let mut correct_pixels = 0u32;
-    let total_pixels = (target_width * target_height) as u32;
+    let total_pixels = target_width * target_height;
 
     for y in 0..target_height {
         for x in 0..target_width {
-            let pixel1 = img1_gray.get_pixel(x, y)[0] > 128;
-            let pixel2 = img2_gray.get_pixel(x, y)[0] > 128;
+            let pixel1 = mask1.is_foreground(x, y);
+            let pixel2 = mask2.is_foreground(x, y);
 
             // 如果两个像素的分类相同（都是前景或都是背景），则为正确
             if pixel1 == pixel2 {
@@ -175,44 +168,12 @@ fn calculate_accuracy(img1_path: &str, img2_path: &str, _window: Option<&tauri::
         }
     }
 
-    Ok(correct_pixels as f64 / total_pixels as f64)
+    correct_pixels as f64 / total_pixels as f64
 }
 
-// 计算两个图片的Dice系数
-fn calculate_dice(img1_path: &str, img2_path: &str, _window: Option<&tauri::Window>) -> Result<f64, String> {
-    let img1 = image::open(img1_path).map_err(|e| format!("无法打开图片1: {}", e))?;
-    let img2 = image::open(img2_path).map_err(|e| format!("无法打开图片2: {}", e))?;
-
-    let (width1, height1) = img1.dimensions();
-    let (width2, height2) = img2.dimensions();
-
-    // 检查图像尺寸是否一致
-    if width1 != width2 || height1 != height2 {
-        let log_message = format!("[Dice系数计算] 图像尺寸不一致: 图片1 {}x{}, 图片2 {}x{} | 图片1: {} | 图片2: {} | 将调整到统一尺寸: {}x{}", 
-            width1, height1, width2, height2, img1_path, img2_path, width1.min(width2), height1.min(height2));
-        println!("{}", log_message);
-    }
-
-    // 处理图像尺寸不一致的情况：将大的图像压缩到小的图像尺寸
-    let (target_width, target_height) = (
-        width1.min(width2),
-        height1.min(height2)
-    );
-
-    let img1_processed = if width1 != target_width || height1 != target_height {
-        img1.resize(target_width, target_height, image::imageops::FilterType::Lanczos3)
-    } else {
-        img1
-    };
-
-    let img2_processed = if width2 != target_width || height2 != target_height {
-        img2.resize(target_width, target_height, image::imageops::FilterType::Lanczos3)
-    } else {
-        img2
-    };
-
-    let img1_gray = img1_processed.to_luma8();
-    let img2_gray = img2_processed.to_luma8();
+// 计算两个掩码的Dice系数，两者尺寸需一致
+fn calculate_dice(mask1: &mask::MaskData, mask2: &mask::MaskData) -> f64 {
+    let (target_width, target_height) = (mask1.width, mask1.height);
 
     let mut intersection = 0u32;
     let mut img1_foreground = 0u32;
@@ -220,8 +181,8 @@ fn calculate_dice(img1_path: &str, img2_path: &str, _window: Option<&tauri::Wind
 
     for y in 0..target_height {
         for x in 0..target_width {
-            let pixel1 = img1_gray.get_pixel(x, y)[0] > 128;
-            let pixel2 = img2_gray.get_pixel(x, y)[0] > 128;
+            let pixel1 = mask1.is_foreground(x, y);
+            let pixel2 = mask2.is_foreground(x, y);
 
             if pixel1 {
                 img1_foreground += 1;
@@ -236,28 +197,349 @@ fn calculate_dice(img1_path: &str, img2_path: &str, _window: Option<&tauri::Wind
     }
 
     // Dice系数公式: 2 * |A ∩ B| / (|A| + |B|)
-    let dice_coefficient = if img1_foreground + img2_foreground == 0 {
+    if img1_foreground + img2_foreground == 0 {
         1.0 // 如果两个图像都没有前景像素，认为完全匹配
     } else {
         (2.0 * intersection as f64) / (img1_foreground + img2_foreground) as f64
+    }
+}
+
+// 每个文件只加载一次、供所有模型共用的GT
+enum GroundTruth {
+    // NIfTI 体数据按路径整体比较
+    Volume(String),
+    Mask {
+        path: String,
+        mask: Result<mask::MaskData, String>,
+    },
+}
+
+impl GroundTruth {
+    // 标注来源按原始图片尺寸栅格化，否则直接读取GT文件（提供调色板时按调色板解码）
+    fn load(
+        annotations: Option<&annotations::AnnotationSet>,
+        palette: Option<&palette::Palette>,
+        gt_path: &str,
+        original_path: Option<&str>,
+        filename: &str,
+    ) -> GroundTruth {
+        if let Some(annotations) = annotations {
+            // 没有原始图片时按标注中记录的图片尺寸栅格化
+            let original_size = original_path.and_then(|p| image::image_dimensions(p).ok());
+            return GroundTruth::Mask {
+                path: annotations.source_path(filename).unwrap_or_else(|| gt_path.to_string()),
+                mask: annotations.rasterize(filename, original_size),
+            };
+        }
+
+        if volume::is_volume_file(gt_path) {
+            return GroundTruth::Volume(gt_path.to_string());
+        }
+
+        GroundTruth::Mask {
+            path: gt_path.to_string(),
+            mask: load_role_mask(gt_path, palette),
+        }
+    }
+
+    fn path(&self) -> &str {
+        match self {
+            GroundTruth::Volume(path) => path,
+            GroundTruth::Mask { path, .. } => path,
+        }
+    }
+}
+
+// 根据结果中记录的 GT 路径读取掩码：COCO 标注文件或 LabelMe 单张 JSON 先按原始图片尺寸栅格化
+fn load_reference_mask(
+    gt_path: &str,
+    filename: &str,
+    original_path: Option<&str>,
+    palette: Option<&palette::Palette>,
+) -> Result<mask::MaskData, String> {
+    if volume::is_volume_file(gt_path) {
+        return Err("体数据不支持叠加图，请按切片查看".to_string());
+    }
+
+    let annotations = if gt_path.to_lowercase().ends_with(".json") {
+        // LabelMe 的 GT 路径是单张图片的 JSON，标注来源为其所在文件夹
+        let labelme_folder = Path::new(gt_path).parent().and_then(|p| p.to_str()).unwrap_or_default();
+        match annotations::AnnotationSet::open_cached(gt_path) {
+            Ok(Some(annotations)) => Some(annotations),
+            _ => annotations::AnnotationSet::open_cached(labelme_folder)?,
+        }
+    } else {
+        None
+    };
+
+    // 标注中以图片文件名记录，优先使用原始图片的文件名
+    let image_name = original_path
+        .unwrap_or(filename)
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or(filename);
+    match GroundTruth::load(annotations.as_deref(), palette, gt_path, original_path, image_name) {
+        GroundTruth::Mask { mask, .. } => mask,
+        GroundTruth::Volume(_) => Err("体数据不支持叠加图，请按切片查看".to_string()),
+    }
+}
+
+// 某个预测与GT的掩码，统一到相同尺寸
+fn load_prediction_pair(
+    filename: &str,
+    gt_path: &str,
+    pred_path: &str,
+    original_path: Option<&str>,
+    palette: Option<&palette::Palette>,
+) -> Result<(mask::MaskData, mask::MaskData), String> {
+    let gt_mask = load_reference_mask(gt_path, filename, original_path, palette)?;
+    if volume::is_volume_file(pred_path) {
+        return Err("体数据不支持叠加图，请按切片查看".to_string());
+    }
+    let pred_mask = load_role_mask(pred_path, palette)?;
+    Ok(mask::align_masks(gt_mask, pred_mask))
+}
+
+fn open_original(original_path: Option<&str>) -> Result<Option<image::DynamicImage>, String> {
+    match original_path {
+        Some(path) => Ok(Some(image::open(path).map_err(|e| format!("无法打开原始图片 {}: {}", path, e))?)),
+        None => Ok(None),
+    }
+}
+
+// 渲染某个预测相对GT的误差叠加图，提供原始图片时叠加在原图上
+fn render_error_overlay(
+    filename: &str,
+    gt_path: &str,
+    pred_path: &str,
+    original_path: Option<&str>,
+    palette: Option<&palette::Palette>,
+    options: &overlay::OverlayOptions,
+) -> Result<image::DynamicImage, String> {
+    let (gt_mask, pred_mask) = load_prediction_pair(filename, gt_path, pred_path, original_path, palette)?;
+    let original = open_original(original_path)?;
+    Ok(overlay::render_error_overlay(&gt_mask, &pred_mask, original.as_ref(), options))
+}
+
+// 渲染GT与各模型预测的轮廓，隐藏的模型不读取；所有掩码统一到其中最小的尺寸，提供原始图片时画在原图上
+fn render_contour_overlay(
+    filename: &str,
+    gt_path: &str,
+    predictions: &[contour::ContourPrediction],
+    original_path: Option<&str>,
+    palette: Option<&palette::Palette>,
+    options: &contour::ContourOptions,
+) -> Result<image::DynamicImage, String> {
+    let gt_mask = load_reference_mask(gt_path, filename, original_path, palette)?;
+    let mut pred_masks = Vec::new();
+    for (index, prediction) in predictions.iter().enumerate() {
+        if !options.visible(&prediction.model) {
+            continue;
+        }
+        if volume::is_volume_file(&prediction.path) {
+            return Err("体数据不支持叠加图，请按切片查看".to_string());
+        }
+        pred_masks.push((index, prediction.model.as_str(), load_role_mask(&prediction.path, palette)?));
+    }
+
+    let width = pred_masks.iter().map(|(_, _, m)| m.width).fold(gt_mask.width, u32::min);
+    let height = pred_masks.iter().map(|(_, _, m)| m.height).fold(gt_mask.height, u32::min);
+    let gt_mask = gt_mask.resize(width, height);
+    let pred_masks: Vec<_> = pred_masks
+        .into_iter()
+        .map(|(index, model, mask)| (index, model, mask.resize(width, height)))
+        .collect();
+    let original = open_original(original_path)?;
+    Ok(contour::render_contours(&gt_mask, &pred_masks, original.as_ref(), options))
+}
+
+// 单个预测相对GT的全部指标
+struct PairScores {
+    iou: f64,
+    accuracy: f64,
+    dice: f64,
+    hd95: Option<f64>,
+    assd: Option<f64>,
+    slices: Option<Vec<volume::SliceScore>>,
+    classes: Option<Vec<palette::ClassScore>>,
+}
+
+// 读取某个角色的掩码，提供调色板时将颜色解码为类别 ID
+fn load_role_mask(path: &str, palette: Option<&palette::Palette>) -> Result<mask::MaskData, String> {
+    match palette {
+        Some(palette) => palette.load_mask(path),
+        None => mask::load_mask(path),
+    }
+}
+
+// 计算预测相对GT的指标，失败时记录日志并记为0
+fn score_prediction(gt: &GroundTruth, pred_path: &str, palette: Option<&palette::Palette>) -> PairScores {
+    let failed = PairScores {
+        iou: 0.0,
+        accuracy: 0.0,
+        dice: 0.0,
+        hd95: None,
+        assd: None,
+        slices: None,
+        classes: None,
+    };
+
+    let gt_mask = match gt {
+        // NIfTI 体数据按整个体积计算，并附带表面距离与逐切片结果
+        GroundTruth::Volume(gt_path) => {
+            return match volume::compare_volumes(gt_path, pred_path) {
+                Ok(metrics) => PairScores {
+                    iou: metrics.iou,
+                    accuracy: metrics.accuracy,
+                    dice: metrics.dice,
+                    hd95: metrics.hd95,
+                    assd: metrics.assd,
+                    slices: Some(metrics.slices),
+                    classes: None,
+                },
+                Err(e) => {
+                    eprintln!("计算体积指标失败: {}", e);
+                    failed
+                }
+            };
+        }
+        GroundTruth::Mask { mask: Ok(mask), .. } => mask,
+        GroundTruth::Mask { mask: Err(e), .. } => {
+            eprintln!("计算指标失败: 无法打开GT: {}", e);
+            return failed;
+        }
     };
 
+    let pred_mask = match load_role_mask(pred_path, palette) {
+        Ok(mask) => mask,
+        Err(e) => {
+            eprintln!("计算指标失败: 无法打开预测: {}", e);
+            return failed;
+        }
+    };
 
+    // 检查图像尺寸是否一致
+    if gt_mask.width != pred_mask.width || gt_mask.height != pred_mask.height {
+        println!(
+            "[指标计算] 图像尺寸不一致: GT {}x{}, 预测 {}x{} | GT: {} | 预测: {} | 将调整到统一尺寸: {}x{}",
+            gt_mask.width,
+            gt_mask.height,
+            pred_mask.width,
+            pred_mask.height,
+            gt.path(),
+            pred_path,
+            gt_mask.width.min(pred_mask.width),
+            gt_mask.height.min(pred_mask.height)
+        );
+    }
 
-    Ok(dice_coefficient)
+    // 处理图像尺寸不一致的情况：将大的图像压缩到小的图像尺寸
+    let (gt_mask, pred_mask) = mask::align_masks(gt_mask.clone(), pred_mask);
+
+    PairScores {
+        iou: calculate_iou(&gt_mask, &pred_mask),
+        accuracy: calculate_accuracy(&gt_mask, &pred_mask),
+        dice: calculate_dice(&gt_mask, &pred_mask),
+        hd95: None,
+        assd: None,
+        slices: None,
+        classes: palette.map(|p| p.class_scores(&gt_mask, &pred_mask)),
+    }
+}
+
+// 将预测与每个标注者及两种共识GT比较，并判断是否处于标注者之间的差异范围内
+fn score_against_references(
+    pred_path: &str,
+    annotators: &[(String, mask::MaskData)],
+    consensus: &consensus::Consensus,
+    palette: Option<&palette::Palette>,
+) -> Option<consensus::MethodReferenceScores> {
+    let pred_mask = match load_role_mask(pred_path, palette) {
+        Ok(mask) => mask,
+        Err(e) => {
+            eprintln!("计算多标注者指标失败: 无法打开预测: {}", e);
+            return None;
+        }
+    };
+
+    let references = annotators.iter().map(|(id, mask)| (id.as_str(), mask)).chain([
+        (consensus::MAJORITY_VOTE, &consensus.majority_vote),
+        (consensus::STAPLE, &consensus.staple),
+    ]);
+    let scores: Vec<consensus::ReferenceScore> = references
+        .map(|(reference, reference_mask)| {
+            let (reference_mask, pred_mask) = mask::align_masks(reference_mask.clone(), pred_mask.clone());
+            consensus::ReferenceScore {
+                reference: reference.to_string(),
+                iou: calculate_iou(&reference_mask, &pred_mask),
+                dice: calculate_dice(&reference_mask, &pred_mask),
+                accuracy: calculate_accuracy(&reference_mask, &pred_mask),
+            }
+        })
+        .collect();
+
+    let mean_annotator_dice =
+        scores[..annotators.len()].iter().map(|s| s.dice).sum::<f64>() / annotators.len() as f64;
+    Some(consensus::MethodReferenceScores {
+        within_human_variability: mean_annotator_dice >= consensus.agreement.mean_dice,
+        mean_annotator_dice,
+        scores,
+    })
+}
+
+impl ComparisonResult {
+    fn new(filename: String) -> Self {
+        ComparisonResult {
+            group: scan::parent_folder(&filename).to_string(),
+            filename,
+            iou_scores: HashMap::new(),
+            accuracy_scores: HashMap::new(),
+            dice_scores: HashMap::new(),
+            hd95_scores: HashMap::new(),
+            assd_scores: HashMap::new(),
+            slice_scores: HashMap::new(),
+            class_scores: HashMap::new(),
+            annotator_agreement: None,
+            reference_scores: HashMap::new(),
+            paths: HashMap::new(),
+        }
+    }
+
+    fn insert_scores(&mut self, name: &str, scores: PairScores) {
+        self.iou_scores.insert(name.to_string(), scores.iou);
+        self.accuracy_scores.insert(name.to_string(), scores.accuracy);
+        self.dice_scores.insert(name.to_string(), scores.dice);
+        if let Some(hd95) = scores.hd95 {
+            self.hd95_scores.insert(name.to_string(), hd95);
+        }
+        if let Some(assd) = scores.assd {
+            self.assd_scores.insert(name.to_string(), assd);
+        }
+        if let Some(slices) = scores.slices {
+            self.slice_scores.insert(name.to_string(), slices);
+        }
+        if let Some(classes) = scores.classes {
+            self.class_scores.insert(name.to_string(), classes);
+        }
+    }
 }
 
 #[tauri::command]
-async fn select_folder(app_handle: tauri::AppHandle) -> Result<String, String> {
+async fn select_folder(app_handle: tauri::AppHandle, default_path: Option<String>) -> Result<String, String> {
     use tauri_plugin_dialog::DialogExt;
     
     // 创建一个 oneshot channel 来等待结果
     let (tx, rx) = tokio::sync::oneshot::channel();
     
-    app_handle
-        .dialog()
-        .file()
-        .set_title("选择文件夹")
+    let mut dialog = app_handle.dialog().file().set_title("选择文件夹");
+    // 重新选择历史记录中的文件夹时从原来的位置打开；原路径为文件时打开其所在的文件夹
+    if let Some(default_path) = default_path.as_deref().map(Path::new) {
+        let directory = if default_path.is_file() { default_path.parent() } else { Some(default_path) };
+        if let Some(directory) = directory.filter(|d| d.is_dir()) {
+            dialog = dialog.set_directory(directory);
+        }
+    }
+    dialog
         .pick_folder(move |folder_path| {
             let _ = tx.send(folder_path);
         });
@@ -266,6 +548,8 @@ async fn select_folder(app_handle: tauri::AppHandle) -> Result<String, String> {
     match rx.await {
         Ok(Some(path)) => {
             if let Some(path_str) = path.as_path().and_then(|p| p.to_str()) {
+                let registry = app_handle.state::<registry::FolderRegistry>();
+                registry.grant(&[Path::new(path_str).to_path_buf()])?;
                 Ok(path_str.to_string())
             } else {
                 Err("无法获取文件夹路径".to_string())
@@ -277,64 +561,160 @@ async fn select_folder(app_handle: tauri::AppHandle) -> Result<String, String> {
 }
 
 #[tauri::command]
-async fn is_file(path: String) -> Result<bool, String> {
-    let path = Path::new(&path);
-    Ok(path.is_file())
+async fn is_file(registry: tauri::State<'_, registry::FolderRegistry>, path: String) -> Result<bool, String> {
+    Ok(registry.resolve_selected(&path)?.is_file())
+}
+
+#[tauri::command]
+async fn get_folder_files(
+    registry: tauri::State<'_, registry::FolderRegistry>,
+    path: String,
+    scan_options: Option<scan::ScanOptions>,
+) -> Result<Vec<String>, String> {
+    registry.resolve_selected(&path)?;
+    list_comparable_files(&path, &scan_options.unwrap_or_default()).map(|(files, _)| files)
+}
+
+#[tauri::command]
+async fn get_volume_info(
+    registry: tauri::State<'_, registry::FolderRegistry>,
+    path: String,
+) -> Result<volume::VolumeInfo, String> {
+    let path = registry.resolve(&path)?;
+    volume::volume_info(&path.to_string_lossy())
 }
 
+// 将标注栅格化为 PNG 数据 URL，供查看标注来源的GT；提供原始图片时按其尺寸栅格化
 #[tauri::command]
-async fn get_folder_files(path: String) -> Result<Vec<String>, String> {
-    get_image_files(&path)
+async fn get_annotation_mask(
+    registry: tauri::State<'_, registry::FolderRegistry>,
+    mut source: String,
+    filename: String,
+    mut original_path: Option<String>,
+) -> Result<String, String> {
+    registry.resolve_all([&mut source].into_iter().chain(original_path.as_mut()))?;
+    let annotations = annotations::AnnotationSet::open_cached(&source)?
+        .ok_or_else(|| format!("不是可识别的标注来源: {}", source))?;
+    let original_size = original_path.and_then(|p| image::image_dimensions(p).ok());
+    let mask = annotations.rasterize(&filename, original_size)?;
+    render::png_data_url(&render::mask_to_image(&mask))
+}
+
+// 从 JSON、文本或索引 PNG 的内嵌调色板加载颜色到类别的映射
+#[tauri::command]
+async fn load_palette(
+    registry: tauri::State<'_, registry::FolderRegistry>,
+    path: String,
+) -> Result<palette::Palette, String> {
+    let path = registry.resolve_selected(&path)?;
+    palette::Palette::load(&path.to_string_lossy())
 }
 
+// 返回体数据某一层切片的 PNG 数据 URL，供逐层查看
 #[tauri::command]
-async fn validate_folders(folders: Vec<String>) -> Result<ValidationResult, String> {
-    if folders.len() < 3 {
-        return Err("至少需要选择3个文件夹".to_string());
+async fn get_volume_slice(
+    registry: tauri::State<'_, registry::FolderRegistry>,
+    path: String,
+    index: usize,
+) -> Result<String, String> {
+    let path = registry.resolve(&path)?;
+    volume::render_slice(&path.to_string_lossy(), index)
+}
+
+// 按角色验证：ID 为 role.id，错误信息中使用显示名称；无参考模式不要求 GT
+fn validate_role_set(
+    registry: &registry::FolderRegistry,
+    roles: &roles::RoleSet,
+    match_rule: Option<matching::MatchRule>,
+    scan_options: Option<scan::ScanOptions>,
+    reference_free: bool,
+) -> Result<ValidationResult, String> {
+    if reference_free {
+        roles.validate_reference_free()?;
+    } else {
+        roles.validate()?;
     }
 
-    let mut folder_files = Vec::new();
-    let folder_types = ["原始图片", "GT", "我的实验数据"];
+    // 按匹配规则将文件名转换为匹配键，共有文件以键的形式返回
+    let matcher = matching::Matcher::new(match_rule.unwrap_or_default())?;
+    let scan_options = scan_options.unwrap_or_default();
+
+    // 划分文件中的条目同时按匹配规则转换为键，例如按主文件名匹配时 case1.png 对应键 case1
+    let split: Option<HashSet<String>> = scan_options.load_split(registry)?.map(|entries| {
+        entries
+            .iter()
+            .flat_map(|entry| [Some(entry.clone()), matcher.key(entry)])
+            .flatten()
+            .collect()
+    });
+    let roles = roles.roles();
+    let mut folder_keys: Vec<HashSet<String>> = Vec::new();
+    let mut filter_stats = HashMap::new();
+    let mut split_removed = HashSet::new();
 
     // 获取每个文件夹的文件列表
-    for (index, folder) in folders.iter().enumerate() {
-        match get_image_files(folder) {
-            Ok(files) => folder_files.push(files),
+    for role in &roles {
+        match list_comparable_files(role.path, &scan_options) {
+            Ok((files, stats)) => {
+                let mut keys: HashSet<String> = matcher.index(&files).into_keys().collect();
+                // 只保留数据划分文件中列出的文件，列表中可以是完整文件名也可以是主文件名
+                if let Some(split) = &split {
+                    keys.retain(|key| {
+                        let listed = split.contains(key) || split.contains(matching::split_extension(key).0);
+                        if !listed {
+                            split_removed.insert(key.clone());
+                        }
+                        listed
+                    });
+                }
+                folder_keys.push(keys);
+                filter_stats.insert(role.id.to_string(), stats);
+            }
+            // 文件夹存在时说明是过滤条件等配置错误，直接返回
+            Err(err) if Path::new(role.path).exists() => return Err(err),
             Err(_err) => {
-                // 根据文件夹位置确定类型
-                let folder_type = if index < folder_types.len() {
-                    folder_types[index]
-                } else {
-                    &format!("对照实验 {}", index - 2)
-                };
-                return Err(format!("\'{}\' 文件夹不存在: {}", folder_type, folder));
+                return Err(format!("\'{}\' 文件夹不存在: {}", role.name, role.path));
             }
         }
     }
 
-    // 找出所有文件夹共有的文件
-    let mut common_files = folder_files[0].clone();
-    for files in &folder_files[1..] {
-        common_files.retain(|f| files.contains(f));
+    // 统计每个键出现在多少个文件夹中：出现在全部文件夹中的为共有文件，只出现在一个文件夹中的为多余文件
+    let mut occurrences: HashMap<&str, usize> = HashMap::new();
+    for keys in &folder_keys {
+        for key in keys {
+            *occurrences.entry(key.as_str()).or_default() += 1;
+        }
     }
 
-    // 检查缺失的文件
+    let mut common_files: Vec<String> = occurrences
+        .iter()
+        .filter(|(_, &count)| count == folder_keys.len())
+        .map(|(key, _)| key.to_string())
+        .collect();
+    common_files.sort();
+
+    // 每个文件夹缺失的文件（其他文件夹中存在而此处没有）与多余的文件（只有此处存在）
     let mut missing_files = HashMap::new();
-    for (i, folder) in folders.iter().enumerate() {
-        let folder_name = Path::new(folder)
-            .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("未知")
-            .to_string();
-
-        let missing: Vec<String> = common_files
+    let mut extra_files = HashMap::new();
+    for (role, keys) in roles.iter().zip(&folder_keys) {
+        let mut missing: Vec<String> = occurrences
+            .keys()
+            .filter(|key| !keys.contains(**key))
+            .map(|key| key.to_string())
+            .collect();
+        let mut extra: Vec<String> = keys
             .iter()
-            .filter(|f| !folder_files[i].contains(f))
+            .filter(|key| occurrences[key.as_str()] == 1)
             .cloned()
             .collect();
 
         if !missing.is_empty() {
-            missing_files.insert(folder_name, missing);
+            missing.sort();
+            missing_files.insert(role.id.to_string(), missing);
+        }
+        if !extra.is_empty() {
+            extra.sort();
+            extra_files.insert(role.id.to_string(), extra);
         }
     }
 
@@ -342,257 +722,790 @@ async fn validate_folders(folders: Vec<String>) -> Result<ValidationResult, Stri
         is_valid: missing_files.is_empty() && !common_files.is_empty(),
         common_files,
         missing_files,
+        extra_files,
+        filter_stats,
+        split_removed: split_removed.len(),
     })
 }
 
-#[derive(Debug, Deserialize)]
-struct ComparisonFolderData {
-    name: String,
-    path: String,
+// 旧版接口：按位置区分的文件夹 [原始图片, GT, 我的实验数据, 对照实验...]
+#[tauri::command]
+async fn validate_folders(
+    app_handle: tauri::AppHandle,
+    folders: Vec<String>,
+    match_rule: Option<matching::MatchRule>,
+    scan_options: Option<scan::ScanOptions>,
+) -> Result<ValidationResult, String> {
+    let roles = roles::RoleSet::from_folders(&folders)?;
+    register_experiment(&app_handle, &experiment_paths(&roles, scan_options.as_ref()))?;
+    validate_role_set(&app_handle.state(), &roles, match_rule, scan_options, false)
 }
 
 #[tauri::command]
-async fn calculate_comparisons_with_progress(
-    window: tauri::Window,
-    original_folder: String,
-    gt_folder: String,
-    my_folder: String,
-    comparison_folders: Vec<ComparisonFolderData>,
-    common_files: Vec<String>,
-) -> Result<Vec<ComparisonResult>, String> {
-    let mut results = Vec::new();
-    let total_files = common_files.len();
+async fn validate_roles(
+    app_handle: tauri::AppHandle,
+    roles: roles::RoleSet,
+    match_rule: Option<matching::MatchRule>,
+    scan_options: Option<scan::ScanOptions>,
+    reference_free: Option<bool>,
+) -> Result<ValidationResult, String> {
+    register_experiment(&app_handle, &experiment_paths(&roles, scan_options.as_ref()))?;
+    validate_role_set(&app_handle.state(), &roles, match_rule, scan_options, reference_free.unwrap_or(false))
+}
 
-    for (index, filename) in common_files.iter().enumerate() {
-        // 发送进度事件
-        let progress = ProgressEvent {
-            current: index,
-            total: total_files,
-            percentage: (index as f64 / total_files as f64) * 100.0,
-            current_file: filename.clone(),
+// 实验登记的路径：各角色的文件夹，以及数据划分文件
+fn experiment_paths(roles: &roles::RoleSet, scan_options: Option<&scan::ScanOptions>) -> Vec<String> {
+    roles
+        .roles()
+        .iter()
+        .map(|role| role.path.to_string())
+        .chain(scan_options.and_then(|options| options.split_file.clone()))
+        .collect()
+}
+
+// 登记为当前实验的文件夹（须为用户选择过的路径），之后的命令、缩略图服务与资源协议只读取其中的文件
+fn register_experiment(app_handle: &tauri::AppHandle, folders: &[String]) -> Result<(), String> {
+    let roots = app_handle.state::<registry::FolderRegistry>().register(folders)?;
+    let scope = app_handle.asset_protocol_scope();
+    for root in roots {
+        let allowed = if root.is_dir() {
+            scope.allow_directory(&root, true)
+        } else {
+            scope.allow_file(&root)
         };
+        allowed.map_err(|e| format!("无法允许显示 {} 中的图片: {}", root.display(), e))?;
+    }
+    Ok(())
+}
 
-        if let Err(e) = window.emit("progress_update", progress) {
-            eprintln!("发送进度事件失败: {}", e);
-        }
+// 尚未经用户选择的文件夹（如本功能之前保存的历史记录），前端据此让用户重新选择
+#[tauri::command]
+async fn ungranted_folders(
+    registry: tauri::State<'_, registry::FolderRegistry>,
+    folders: Vec<String>,
+) -> Result<Vec<String>, String> {
+    registry.ungranted(&folders)
+}
 
-        let original_path = format!("{}/{}", original_folder, filename);
-        let gt_path = format!("{}/{}", gt_folder, filename);
-        let my_path = format!("{}/{}", my_folder, filename);
+// 命令中的角色路径必须是当前实验登记的文件夹
+fn check_role_paths(registry: &registry::FolderRegistry, roles: &roles::RoleSet) -> Result<(), String> {
+    registry.check_all(roles.roles().iter().map(|role| role.path))
+}
 
-        let mut iou_scores = HashMap::new();
-        let mut accuracy_scores = HashMap::new();
-        let mut dice_scores = HashMap::new();
-        let mut paths = HashMap::new();
+// 深度预检：检查所有文件夹共有文件的可读性、尺寸、通道数与取值；full_decode 为 false 时只读取文件头
+#[tauri::command]
+async fn preflight_check(
+    roles: roles::RoleSet,
+    match_rule: Option<matching::MatchRule>,
+    scan_options: Option<scan::ScanOptions>,
+    palette: Option<palette::Palette>,
+    full_decode: Option<bool>,
+    registry: tauri::State<'_, registry::FolderRegistry>,
+) -> Result<Vec<preflight::FolderReport>, String> {
+    roles.validate()?;
+    check_role_paths(&registry, &roles)?;
+    let matcher = matching::Matcher::new(match_rule.unwrap_or_default())?;
+    let scan_options = scan_options.unwrap_or_default();
+
+    // 以第一个 GT 的尺寸为基准；GT 为标注文件时没有掩码文件可检查，改以原始图片为基准
+    let primary_gt = &roles.primary_gt().id;
+    let mut inputs = Vec::new();
+    let mut reference = 0;
+    for role in roles.roles() {
+        if role.kind == roles::RoleKind::GroundTruth && annotations::AnnotationSet::open(role.path)?.is_some() {
+            continue;
+        }
+        if role.id == primary_gt || (role.kind == roles::RoleKind::Original && inputs.is_empty()) {
+            reference = inputs.len();
+        }
+        let (files, _) = list_comparable_files(role.path, &scan_options)?;
+        inputs.push(preflight::FolderInput {
+            role: role.id.to_string(),
+            folder: role.path.to_string(),
+            files: matcher.index(&files),
+            is_mask: role.kind != roles::RoleKind::Original,
+        });
+    }
 
-        // 添加原始图片、GT和我的实验数据路径
-        paths.insert("原始图片".to_string(), original_path.clone());
-        paths.insert("GT".to_string(), gt_path.clone());
-        paths.insert("我的结果".to_string(), my_path.clone());
+    let Some((first, rest)) = inputs.split_first() else {
+        return Err("没有需要检查的文件夹".to_string());
+    };
+    let mut keys: Vec<String> = first
+        .files
+        .keys()
+        .filter(|key| rest.iter().all(|input| input.files.contains_key(*key)))
+        .cloned()
+        .collect();
+    keys.sort();
+
+    Ok(preflight::check_folders(
+        &inputs,
+        reference,
+        &keys,
+        palette.as_ref(),
+        full_decode.unwrap_or(false),
+    ))
+}
 
-        // 计算我的结果与GT的IOU和准确率
-        match calculate_iou(&gt_path, &my_path, Some(&window)) {
-            Ok(iou) => {
-                iou_scores.insert("我的结果".to_string(), iou);
-            }
-            Err(e) => {
-                eprintln!("计算IOU失败: {}", e);
-                iou_scores.insert("我的结果".to_string(), 0.0);
-            }
-        }
+#[derive(Debug, Deserialize)]
+struct ComparisonFolderData {
+    name: String,
+    path: String,
+}
 
-        match calculate_accuracy(&gt_path, &my_path, Some(&window)) {
-            Ok(accuracy) => {
-                accuracy_scores.insert("我的结果".to_string(), accuracy);
-            }
-            Err(e) => {
-                eprintln!("计算准确率失败: {}", e);
-                accuracy_scores.insert("我的结果".to_string(), 0.0);
-            }
-        }
+// 一次对比计算的全部输入
+struct ComparisonJob {
+    roles: roles::RoleSet,
+    common_files: Vec<String>,
+    palette: Option<palette::Palette>,
+    match_rule: Option<matching::MatchRule>,
+    scan_options: Option<scan::ScanOptions>,
+}
 
-        match calculate_dice(&gt_path, &my_path, Some(&window)) {
-            Ok(dice) => {
-                dice_scores.insert("我的结果".to_string(), dice);
-            }
-            Err(e) => {
-                eprintln!("计算Dice系数失败: {}", e);
-                dice_scores.insert("我的结果".to_string(), 0.0);
-            }
-        }
+// 按匹配键在文件夹中定位实际文件，键不在索引中时直接作为文件名
+fn resolve_path(folder: &str, index: &HashMap<String, String>, key: &str) -> String {
+    let filename = index.get(key).map(String::as_str).unwrap_or(key);
+    format!("{}/{}", folder, filename)
+}
 
-        // 计算对比数据与GT的IOU和准确率
-        for comp_folder in comparison_folders.iter() {
-            let comp_path = format!("{}/{}", comp_folder.path, filename);
-            let comp_name = comp_folder.name.clone();
+// 每个角色的文件夹建立 匹配键 -> 实际文件名 的索引，文件夹无法读取时索引为空
+fn index_roles(
+    roles: &[roles::Role],
+    match_rule: Option<matching::MatchRule>,
+    scan_options: Option<scan::ScanOptions>,
+) -> Result<Vec<HashMap<String, String>>, String> {
+    let matcher = matching::Matcher::new(match_rule.unwrap_or_default())?;
+    let scan_options = scan_options.unwrap_or_default();
+    Ok(roles
+        .iter()
+        .map(|role| {
+            list_comparable_files(role.path, &scan_options)
+                .map(|(files, _)| matcher.index(&files))
+                .unwrap_or_default()
+        })
+        .collect())
+}
 
-            paths.insert(comp_name.clone(), comp_path.clone());
+// 发送进度事件，current 等于 total 时表示计算完成
+fn emit_progress(window: &tauri::Window, current: usize, total: usize, current_file: &str) {
+    let progress = ProgressEvent {
+        current,
+        total,
+        percentage: if current >= total { 100.0 } else { (current as f64 / total as f64) * 100.0 },
+        current_file: if current >= total { "计算完成".to_string() } else { current_file.to_string() },
+    };
+    if let Err(e) = window.emit("progress_update", progress) {
+        eprintln!("发送进度事件失败: {}", e);
+    }
+}
 
-            // 计算IOU
-            match calculate_iou(&gt_path, &comp_path, Some(&window)) {
-                Ok(iou) => {
-                    iou_scores.insert(comp_name.clone(), iou);
-                }
-                Err(e) => {
-                    eprintln!("计算IOU失败: {}", e);
-                    iou_scores.insert(comp_name.clone(), 0.0);
+// 执行对比计算，所有方法均相对第一个 GT 来源计算指标；提供窗口时发送进度事件
+fn run_comparisons(job: ComparisonJob, window: Option<&tauri::Window>) -> Result<Vec<ComparisonResult>, String> {
+    job.roles.validate()?;
+    let mut results = Vec::new();
+    let total_files = job.common_files.len();
+    let primary_gt = job.roles.primary_gt();
+    // GT 可以是标注文件或标注文件夹，每个 GT 来源只解析一次
+    let gt_annotations: HashMap<&str, annotations::AnnotationSet> = job
+        .roles
+        .ground_truths
+        .iter()
+        .filter_map(|gt| match annotations::AnnotationSet::open(&gt.path) {
+            Ok(annotations) => annotations.map(|a| Ok((gt.id.as_str(), a))),
+            Err(e) => Some(Err(e)),
+        })
+        .collect::<Result<_, String>>()?;
+    let palette = job.palette.as_ref();
+
+    // 每个角色的文件夹建立 匹配键 -> 实际文件名 的索引
+    let roles = job.roles.roles();
+    let indexes = index_roles(&roles, job.match_rule, job.scan_options)?;
+
+    for (index, filename) in job.common_files.iter().enumerate() {
+        if let Some(window) = window {
+            emit_progress(window, index, total_files, filename);
+        }
+
+        let mut result = ComparisonResult::new(filename.clone());
+        let mut original_path = None;
+        let mut gts = Vec::new();
+
+        // 记录原始图片与所有 GT 来源的路径，并加载每个 GT
+        for (role, role_index) in roles.iter().zip(&indexes) {
+            let path = resolve_path(role.path, role_index, filename);
+            match role.kind {
+                roles::RoleKind::Original => original_path = Some(path.clone()),
+                roles::RoleKind::GroundTruth => {
+                    let gt_filename = role_index.get(filename).unwrap_or(filename);
+                    let loaded = GroundTruth::load(
+                        gt_annotations.get(role.id),
+                        palette,
+                        &path,
+                        original_path.as_deref(),
+                        gt_filename,
+                    );
+                    result.paths.insert(role.id.to_string(), loaded.path().to_string());
+                    gts.push((role.id, loaded));
+                    continue;
                 }
+                roles::RoleKind::Method => {}
             }
+            result.paths.insert(role.id.to_string(), path);
+        }
 
-            // 计算准确率
-            match calculate_accuracy(&gt_path, &comp_path, Some(&window)) {
-                Ok(accuracy) => {
-                    accuracy_scores.insert(comp_name.clone(), accuracy);
-                }
-                Err(e) => {
-                    eprintln!("计算准确率失败: {}", e);
-                    accuracy_scores.insert(comp_name.clone(), 0.0);
-                }
+        // 计算每个方法与第一个GT的指标
+        let methods: Vec<&roles::Role> = roles.iter().filter(|r| r.kind == roles::RoleKind::Method).collect();
+        if let Some((_, gt)) = gts.iter().find(|(id, _)| *id == primary_gt.id) {
+            for role in &methods {
+                result.insert_scores(role.id, score_prediction(gt, &result.paths[role.id], palette));
             }
+        }
 
-            // 计算Dice系数
-            match calculate_dice(&gt_path, &comp_path, Some(&window)) {
-                Ok(dice) => {
-                    dice_scores.insert(comp_name.clone(), dice);
-                }
-                Err(e) => {
-                    eprintln!("计算Dice系数失败: {}", e);
-                    dice_scores.insert(comp_name.clone(), 0.0);
+        // 多个标注者时计算一致性，并将每个方法与各标注者及共识GT比较；体数据与无法读取的GT不参与
+        let annotators: Vec<(String, mask::MaskData)> = gts
+            .iter()
+            .filter_map(|(id, gt)| match gt {
+                GroundTruth::Mask { mask: Ok(mask), .. } => Some((id.to_string(), mask.clone())),
+                _ => None,
+            })
+            .collect();
+        if annotators.len() > 1 && annotators.len() == gts.len() {
+            if let Some(consensus) = consensus::analyze(&annotators) {
+                for role in &methods {
+                    if let Some(scores) =
+                        score_against_references(&result.paths[role.id], &annotators, &consensus, palette)
+                    {
+                        result.reference_scores.insert(role.id.to_string(), scores);
+                    }
                 }
+                result.annotator_agreement = Some(consensus.agreement);
             }
         }
 
-        results.push(ComparisonResult {
-            filename: filename.clone(),
-            iou_scores,
-            accuracy_scores,
-            dice_scores,
-            paths,
-        });
+        results.push(result);
     }
 
-    // 发送完成事件
-    let final_progress = ProgressEvent {
-        current: total_files,
-        total: total_files,
-        percentage: 100.0,
-        current_file: "计算完成".to_string(),
-    };
-
-    if let Err(e) = window.emit("progress_update", final_progress) {
-        eprintln!("发送完成事件失败: {}", e);
+    if let Some(window) = window {
+        // 发送完成事件
+        emit_progress(window, total_files, total_files, "");
     }
 
     Ok(results)
 }
 
+// 旧版接口的文件夹参数转换为角色
+fn legacy_roles(
+    original_folder: String,
+    gt_folder: String,
+    my_folder: String,
+    comparison_folders: Vec<ComparisonFolderData>,
+) -> roles::RoleSet {
+    let comparisons = comparison_folders.into_iter().map(|f| (f.name, f.path)).collect();
+    roles::RoleSet::from_legacy(original_folder, gt_folder, my_folder, comparisons)
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn calculate_comparisons_with_progress(
+    window: tauri::Window,
+    original_folder: String,
+    gt_folder: String,
+    my_folder: String,
+    comparison_folders: Vec<ComparisonFolderData>,
+    common_files: Vec<String>,
+    palette: Option<palette::Palette>,
+    match_rule: Option<matching::MatchRule>,
+    scan_options: Option<scan::ScanOptions>,
+    registry: tauri::State<'_, registry::FolderRegistry>,
+) -> Result<Vec<ComparisonResult>, String> {
+    let job = ComparisonJob {
+        roles: legacy_roles(original_folder, gt_folder, my_folder, comparison_folders),
+        common_files,
+        palette,
+        match_rule,
+        scan_options,
+    };
+    check_role_paths(&registry, &job.roles)?;
+    run_comparisons(job, Some(&window))
+}
+
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 async fn calculate_comparisons(
     original_folder: String,
     gt_folder: String,
     my_folder: String,
     comparison_folders: Vec<ComparisonFolderData>,
     common_files: Vec<String>,
+    palette: Option<palette::Palette>,
+    match_rule: Option<matching::MatchRule>,
+    scan_options: Option<scan::ScanOptions>,
+    registry: tauri::State<'_, registry::FolderRegistry>,
 ) -> Result<Vec<ComparisonResult>, String> {
-    let mut results = Vec::new();
+    let job = ComparisonJob {
+        roles: legacy_roles(original_folder, gt_folder, my_folder, comparison_folders),
+        common_files,
+        palette,
+        match_rule,
+        scan_options,
+    };
+    check_role_paths(&registry, &job.roles)?;
+    run_comparisons(job, None)
+}
 
-    for filename in common_files {
-        let original_path = format!("{}/{}", original_folder, filename);
-        let gt_path = format!("{}/{}", gt_folder, filename);
-        let my_path = format!("{}/{}", my_folder, filename);
-
-        let mut iou_scores = HashMap::new();
-        let mut accuracy_scores = HashMap::new();
-        let mut dice_scores = HashMap::new();
-        let mut paths = HashMap::new();
-
-        // 添加原始图片、GT和我的实验数据路径
-        paths.insert("原始图片".to_string(), original_path.clone());
-        paths.insert("GT".to_string(), gt_path.clone());
-        paths.insert("我的结果".to_string(), my_path.clone());
-
-        // 计算我的结果与GT的IOU和准确率
-        match calculate_iou(&gt_path, &my_path, None) {
-            Ok(iou) => {
-                iou_scores.insert("我的结果".to_string(), iou);
-            }
-            Err(e) => {
-                eprintln!("计算IOU失败: {}", e);
-                iou_scores.insert("我的结果".to_string(), 0.0);
-            }
-        }
+// 按角色计算对比结果，路径与指标以角色 ID 为键
+#[tauri::command]
+async fn calculate_role_comparisons(
+    window: tauri::Window,
+    roles: roles::RoleSet,
+    common_files: Vec<String>,
+    palette: Option<palette::Palette>,
+    match_rule: Option<matching::MatchRule>,
+    scan_options: Option<scan::ScanOptions>,
+    registry: tauri::State<'_, registry::FolderRegistry>,
+) -> Result<Vec<ComparisonResult>, String> {
+    check_role_paths(&registry, &roles)?;
+    let job = ComparisonJob {
+        roles,
+        common_files,
+        palette,
+        match_rule,
+        scan_options,
+    };
+    run_comparisons(job, Some(&window))
+}
 
-        match calculate_accuracy(&gt_path, &my_path, None) {
-            Ok(accuracy) => {
-                accuracy_scores.insert("我的结果".to_string(), accuracy);
-            }
-            Err(e) => {
-                eprintln!("计算准确率失败: {}", e);
-                accuracy_scores.insert("我的结果".to_string(), 0.0);
-            }
-        }
+// 无参考模式中某个方法的预测：体数据按路径整体比较，其余读取为掩码
+enum Prediction {
+    Volume(String),
+    Mask(mask::MaskData),
+}
 
-        match calculate_dice(&gt_path, &my_path, None) {
-            Ok(dice) => {
-                dice_scores.insert("我的结果".to_string(), dice);
-            }
-            Err(e) => {
-                eprintln!("计算Dice系数失败: {}", e);
-                dice_scores.insert("我的结果".to_string(), 0.0);
-            }
+// 两个预测之间的 (IOU, Dice)
+fn pair_overlap(a: &Prediction, b: &Prediction) -> Result<(f64, f64), String> {
+    match (a, b) {
+        (Prediction::Volume(a), Prediction::Volume(b)) => {
+            let metrics = volume::compare_volumes(a, b)?;
+            Ok((metrics.iou, metrics.dice))
+        }
+        (Prediction::Mask(a), Prediction::Mask(b)) => {
+            let (a, b) = mask::align_masks(a.clone(), b.clone());
+            Ok((calculate_iou(&a, &b), calculate_dice(&a, &b)))
         }
+        _ => Err("不能将体数据与二维掩码比较".to_string()),
+    }
+}
+
+// 无参考模式：没有 GT 时计算所有方法两两之间的一致性，图片按分歧从大到小排序
+#[tauri::command]
+async fn calculate_agreement(
+    window: tauri::Window,
+    roles: roles::RoleSet,
+    common_files: Vec<String>,
+    palette: Option<palette::Palette>,
+    match_rule: Option<matching::MatchRule>,
+    scan_options: Option<scan::ScanOptions>,
+    registry: tauri::State<'_, registry::FolderRegistry>,
+) -> Result<agreement::AgreementReport, String> {
+    roles.validate_reference_free()?;
+    check_role_paths(&registry, &roles)?;
+    let palette = palette.as_ref();
+    let roles = roles.roles();
+    let indexes = index_roles(&roles, match_rule, scan_options)?;
+    let methods: Vec<usize> = (0..roles.len())
+        .filter(|&i| roles[i].kind == roles::RoleKind::Method)
+        .collect();
 
-        // 计算对比数据与GT的IOU和准确率
-        for comp_folder in comparison_folders.iter() {
-            let comp_path = format!("{}/{}", comp_folder.path, filename);
-            let comp_name = comp_folder.name.clone();
+    let total_files = common_files.len();
+    let mut images = Vec::new();
+    for (index, filename) in common_files.iter().enumerate() {
+        emit_progress(&window, index, total_files, filename);
 
-            paths.insert(comp_name.clone(), comp_path.clone());
+        let paths: HashMap<String, String> = roles
+            .iter()
+            .zip(&indexes)
+            .map(|(role, role_index)| (role.id.to_string(), resolve_path(role.path, role_index, filename)))
+            .collect();
 
-            // 计算IOU
-            match calculate_iou(&gt_path, &comp_path, None) {
-                Ok(iou) => {
-                    iou_scores.insert(comp_name.clone(), iou);
+        let mut errors = Vec::new();
+        let predictions: Vec<Option<Prediction>> = methods
+            .iter()
+            .map(|&i| {
+                let path = &paths[roles[i].id];
+                if volume::is_volume_file(path) {
+                    return Some(Prediction::Volume(path.clone()));
                 }
-                Err(e) => {
-                    eprintln!("计算IOU失败: {}", e);
-                    iou_scores.insert(comp_name.clone(), 0.0);
+                load_role_mask(path, palette)
+                    .map(Prediction::Mask)
+                    .map_err(|e| errors.push(e))
+                    .ok()
+            })
+            .collect();
+
+        let mut matrix = agreement::AgreementMatrix::identity(methods.len());
+        for i in 0..predictions.len() {
+            for j in i + 1..predictions.len() {
+                // 无法读取的配对跳过，不计为 0
+                let (Some(a), Some(b)) = (&predictions[i], &predictions[j]) else {
+                    continue;
+                };
+                match pair_overlap(a, b) {
+                    Ok((iou, dice)) => matrix.set(i, j, iou, dice),
+                    Err(e) => errors.push(e),
                 }
             }
+        }
 
-            // 计算准确率
-            match calculate_accuracy(&gt_path, &comp_path, None) {
-                Ok(accuracy) => {
-                    accuracy_scores.insert(comp_name.clone(), accuracy);
-                }
-                Err(e) => {
-                    eprintln!("计算准确率失败: {}", e);
-                    accuracy_scores.insert(comp_name.clone(), 0.0);
+        let group = scan::parent_folder(filename).to_string();
+        images.push(agreement::ImageAgreement::new(filename.clone(), group, matrix, paths, errors));
+    }
+
+    // 发送完成事件
+    emit_progress(&window, total_files, total_files, "");
+
+    let method_ids = methods.iter().map(|&i| roles[i].id.to_string()).collect();
+    Ok(agreement::build_report(method_ids, images))
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorOverlayRequest {
+    filename: String,
+    gt_path: String,
+    pred_path: String,
+    #[serde(default)]
+    original_path: Option<String>,
+    #[serde(default)]
+    palette: Option<palette::Palette>,
+    #[serde(default)]
+    options: overlay::OverlayOptions,
+}
+
+// 返回误差叠加图（TP、FP、FN 着色）的 PNG 数据 URL，供查看器显示
+#[tauri::command]
+async fn get_error_overlay(
+    registry: tauri::State<'_, registry::FolderRegistry>,
+    mut request: ErrorOverlayRequest,
+) -> Result<String, String> {
+    let paths = [&mut request.gt_path, &mut request.pred_path];
+    registry.resolve_all(paths.into_iter().chain(request.original_path.as_mut()))?;
+    let image = render_error_overlay(
+        &request.filename,
+        &request.gt_path,
+        &request.pred_path,
+        request.original_path.as_deref(),
+        request.palette.as_ref(),
+        &request.options,
+    )?;
+    render::png_data_url(&image)
+}
+
+#[derive(Debug, Deserialize)]
+struct ContourOverlayRequest {
+    filename: String,
+    gt_path: String,
+    // 按显示顺序排列，未单独设置颜色的模型按此顺序分配颜色
+    predictions: Vec<contour::ContourPrediction>,
+    #[serde(default)]
+    original_path: Option<String>,
+    #[serde(default)]
+    palette: Option<palette::Palette>,
+    #[serde(default)]
+    options: contour::ContourOptions,
+}
+
+// 返回GT与各模型预测轮廓叠加在原图上的 PNG 数据 URL，供查看器显示
+#[tauri::command]
+async fn get_contour_overlay(
+    registry: tauri::State<'_, registry::FolderRegistry>,
+    mut request: ContourOverlayRequest,
+) -> Result<String, String> {
+    let predictions = request.predictions.iter_mut().map(|p| &mut p.path);
+    let paths = [&mut request.gt_path].into_iter().chain(predictions);
+    registry.resolve_all(paths.chain(request.original_path.as_mut()))?;
+    let image = render_contour_overlay(
+        &request.filename,
+        &request.gt_path,
+        &request.predictions,
+        request.original_path.as_deref(),
+        request.palette.as_ref(),
+        &request.options,
+    )?;
+    render::png_data_url(&image)
+}
+
+#[derive(Debug, Deserialize)]
+struct DifferenceRequest {
+    filename: String,
+    path_a: String,
+    path_b: String,
+    #[serde(default)]
+    original_path: Option<String>,
+    #[serde(default)]
+    palette: Option<palette::Palette>,
+    #[serde(default)]
+    options: overlay::DifferenceOptions,
+}
+
+// 任意两个角色（ComparisonResult.paths 中的路径）之间的差异图，并统计前景不一致的像素数
+#[tauri::command]
+async fn get_difference_image(
+    registry: tauri::State<'_, registry::FolderRegistry>,
+    mut request: DifferenceRequest,
+) -> Result<overlay::DifferenceImage, String> {
+    let paths = [&mut request.path_a, &mut request.path_b];
+    registry.resolve_all(paths.into_iter().chain(request.original_path.as_mut()))?;
+    let palette = request.palette.as_ref();
+    let original_path = request.original_path.as_deref();
+    let mask_a = load_reference_mask(&request.path_a, &request.filename, original_path, palette)?;
+    let mask_b = load_reference_mask(&request.path_b, &request.filename, original_path, palette)?;
+    let (mask_a, mask_b) = mask::align_masks(mask_a, mask_b);
+    let original = open_original(original_path)?;
+
+    let image = overlay::render_error_overlay(&mask_a, &mask_b, original.as_ref(), &request.options.as_overlay());
+    Ok(overlay::DifferenceImage {
+        image: render::png_data_url(&image)?,
+        differing_pixels: overlay::count_differences(&mask_a, &mask_b),
+        total_pixels: mask_a.width as usize * mask_a.height as usize,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+struct ProbeRequest {
+    filename: String,
+    // 角色 ID -> 文件路径，通常为 ComparisonResult.paths
+    paths: HashMap<String, String>,
+    x: u32,
+    y: u32,
+    // 坐标所在的参考尺寸 [宽, 高]（如界面中显示的图片尺寸），各文件按比例换算；为空时直接使用文件坐标
+    #[serde(default)]
+    reference_size: Option<[u32; 2]>,
+    // 体数据的切片索引，坐标与 get_volume_slice 显示的图片一致
+    #[serde(default)]
+    slice: Option<usize>,
+    #[serde(default)]
+    palette: Option<palette::Palette>,
+    // paths 中原始图片对应的键，默认取自 roles；原始图片不是掩码，不按调色板解码
+    #[serde(default)]
+    original_key: Option<String>,
+    #[serde(default)]
+    roles: Option<roles::RoleSet>,
+}
+
+impl ProbeRequest {
+    fn is_original(&self, role: &str) -> bool {
+        let key = match &self.original_key {
+            Some(key) => Some(key.as_str()),
+            None => self.roles.as_ref().and_then(|roles| roles.original.as_ref()).map(|source| source.id.as_str()),
+        };
+        key.is_some_and(|key| !key.is_empty() && key == role)
+    }
+}
+
+// 读取单个角色在某个坐标上的值
+fn probe_role(request: &ProbeRequest, role: &str, path: &str) -> Result<probe::PixelProbe, String> {
+    let mut result = probe::PixelProbe {
+        role: role.to_string(),
+        ..Default::default()
+    };
+
+    if volume::is_volume_file(path) {
+        let (x, y) = (request.x, request.y);
+        let (value, foreground) = volume::probe(path, x as usize, y as usize, request.slice.unwrap_or(0))?;
+        result.x = x;
+        result.y = y;
+        result.raw = vec![value];
+        result.value = Some(value);
+        result.foreground = Some(foreground);
+        return Ok(result);
+    }
+
+    // 原始图片不按调色板解码；普通图片只解码一次，同时得到原始通道值（如 RGB）与掩码
+    let palette = request.palette.as_ref().filter(|_| !request.is_original(role));
+    let image = if mask::decodes_as_image(path) {
+        Some(image::open(path).map_err(|e| format!("无法打开图片 {}: {}", path, e))?)
+    } else {
+        None
+    };
+    let mask = match (&image, palette) {
+        (Some(image), Some(palette)) => palette.image_to_mask(image),
+        (Some(image), None) => mask::image_to_mask(image),
+        (None, _) => load_reference_mask(path, &request.filename, None, palette)?,
+    };
+
+    let (x, y) = probe::scale_point(request.x, request.y, request.reference_size, mask.width, mask.height)?;
+    result.x = x;
+    result.y = y;
+    result.value = Some(mask.value(x, y));
+    result.foreground = Some(mask.is_foreground(x, y));
+    if let Some(palette) = palette {
+        let class_id = mask.value(x, y) as u32;
+        result.class_id = Some(class_id);
+        result.class_name = Some(palette.class_name(class_id));
+    }
+    result.raw = match &image {
+        Some(image) => probe::raw_pixel(image, x, y),
+        None => vec![mask.value(x, y)],
+    };
+    Ok(result)
+}
+
+// 像素探针：返回每个角色在 (x, y) 处的原始值与二值化结果，供悬停查看
+#[tauri::command]
+async fn probe_pixel(
+    registry: tauri::State<'_, registry::FolderRegistry>,
+    mut request: ProbeRequest,
+) -> Result<Vec<probe::PixelProbe>, String> {
+    registry.resolve_all(request.paths.values_mut())?;
+    let mut roles: Vec<(&String, &String)> = request.paths.iter().collect();
+    roles.sort();
+
+    Ok(roles
+        .into_iter()
+        .map(|(role, path)| {
+            probe_role(&request, role, path).unwrap_or_else(|e| probe::PixelProbe {
+                role: role.clone(),
+                x: request.x,
+                y: request.y,
+                error: Some(e),
+                ..Default::default()
+            })
+        })
+        .collect())
+}
+
+#[derive(Debug, Deserialize)]
+struct HeatmapRequest {
+    roles: roles::RoleSet,
+    common_files: Vec<String>,
+    #[serde(default)]
+    palette: Option<palette::Palette>,
+    #[serde(default)]
+    match_rule: Option<matching::MatchRule>,
+    #[serde(default)]
+    scan_options: Option<scan::ScanOptions>,
+    // 统一网格的 [宽, 高]，默认 256x256，每边不超过 4096
+    #[serde(default)]
+    grid_size: Option<[u32; 2]>,
+    // 提供时将每个模型的 FP/FN 热力图保存为 PNG
+    #[serde(default)]
+    export_folder: Option<String>,
+}
+
+// 数据集级误差热力图：累计每个方法相对第一个 GT 的逐像素 FP/FN 频率，体数据与无法读取的文件跳过
+#[tauri::command]
+async fn compute_error_heatmaps(
+    window: tauri::Window,
+    registry: tauri::State<'_, registry::FolderRegistry>,
+    request: HeatmapRequest,
+) -> Result<Vec<heatmap::ModelHeatmap>, String> {
+    request.roles.validate()?;
+    check_role_paths(&registry, &request.roles)?;
+    let palette = request.palette.as_ref();
+    let primary_gt = request.roles.primary_gt();
+    let gt_annotations = annotations::AnnotationSet::open(&primary_gt.path)?;
+    let roles = request.roles.roles();
+    let indexes = index_roles(&roles, request.match_rule, request.scan_options)?;
+    let grid_size = request.grid_size.unwrap_or(heatmap::DEFAULT_GRID_SIZE);
+
+    let methods: Vec<usize> = (0..roles.len())
+        .filter(|&i| roles[i].kind == roles::RoleKind::Method)
+        .collect();
+    let mut accumulators: Vec<heatmap::ErrorAccumulator> = methods
+        .iter()
+        .map(|_| heatmap::ErrorAccumulator::new(grid_size))
+        .collect::<Result<_, _>>()?;
+
+    let total_files = request.common_files.len();
+    for (index, filename) in request.common_files.iter().enumerate() {
+        emit_progress(&window, index, total_files, filename);
+
+        // 原始图片在角色列表中排在 GT 之前，用于标注来源的栅格化尺寸
+        let mut original_path = None;
+        let mut gt = None;
+        for (role, role_index) in roles.iter().zip(&indexes) {
+            let path = resolve_path(role.path, role_index, filename);
+            match role.kind {
+                roles::RoleKind::Original => original_path = Some(path),
+                roles::RoleKind::GroundTruth if role.id == primary_gt.id => {
+                    let gt_filename = role_index.get(filename).unwrap_or(filename);
+                    gt = Some(GroundTruth::load(
+                        gt_annotations.as_ref(),
+                        palette,
+                        &path,
+                        original_path.as_deref(),
+                        gt_filename,
+                    ));
                 }
+                _ => {}
             }
+        }
+        let Some(GroundTruth::Mask { mask: Ok(gt_mask), .. }) = gt else {
+            continue;
+        };
 
-            // 计算Dice系数
-            match calculate_dice(&gt_path, &comp_path, None) {
-                Ok(dice) => {
-                    dice_scores.insert(comp_name.clone(), dice);
-                }
-                Err(e) => {
-                    eprintln!("计算Dice系数失败: {}", e);
-                    dice_scores.insert(comp_name.clone(), 0.0);
+        for (&role_position, accumulator) in methods.iter().zip(accumulators.iter_mut()) {
+            let role = &roles[role_position];
+            let pred_path = resolve_path(role.path, &indexes[role_position], filename);
+            if volume::is_volume_file(&pred_path) {
+                continue;
+            }
+            match load_role_mask(&pred_path, palette) {
+                Ok(pred_mask) => {
+                    let (gt_mask, pred_mask) = mask::align_masks(gt_mask.clone(), pred_mask);
+                    accumulator.add(&gt_mask, &pred_mask);
                 }
+                Err(e) => eprintln!("误差热力图跳过无法读取的预测: {}", e),
+            }
+        }
+    }
+    emit_progress(&window, total_files, total_files, "");
+
+    let mut heatmaps = Vec::new();
+    for (&role_position, accumulator) in methods.iter().zip(&accumulators) {
+        let role = &roles[role_position];
+        let (heatmap, [fp_image, fn_image]) = accumulator.finish(role.id)?;
+        if let Some(folder) = &request.export_folder {
+            let safe_name = role.id.replace(['/', '\\', ':'], "_");
+            for (kind, image) in [("fp", fp_image), ("fn", fn_image)] {
+                let path = Path::new(folder).join(format!("{}_{}_heatmap.png", safe_name, kind));
+                let path = registry.resolve_output(&path)?;
+                image
+                    .save(&path)
+                    .map_err(|e| format!("保存热力图失败 {}: {}", path.display(), e))?;
             }
         }
+        heatmaps.push(heatmap);
+    }
+    Ok(heatmaps)
+}
 
-        results.push(ComparisonResult {
-            filename,
-            iou_scores,
-            accuracy_scores,
-            dice_scores,
-            paths,
-        });
+// 单张图片的模型共识图：每个像素预测为前景的模型占比；提供 output_path 时同时保存为 PNG
+#[tauri::command]
+async fn get_consensus_map(
+    registry: tauri::State<'_, registry::FolderRegistry>,
+    mut pred_paths: Vec<String>,
+    palette: Option<palette::Palette>,
+    output_path: Option<String>,
+) -> Result<heatmap::ConsensusMap, String> {
+    registry.resolve_all(&mut pred_paths)?;
+    let predictions = pred_paths
+        .iter()
+        .map(|path| load_role_mask(path, palette.as_ref()))
+        .collect::<Result<Vec<_>, String>>()?;
+    let image = heatmap::consensus_image(&predictions).ok_or("至少需要一个预测")?;
+    if let Some(output_path) = &output_path {
+        let output_path = registry.resolve_output(Path::new(output_path))?;
+        image
+            .save(&output_path)
+            .map_err(|e| format!("保存共识图失败 {}: {}", output_path.display(), e))?;
     }
 
-    Ok(results)
+    Ok(heatmap::ConsensusMap {
+        models: predictions.len(),
+        width: image.width(),
+        height: image.height(),
+        image: render::png_data_url(&image)?,
+    })
 }
 
 #[tauri::command]
@@ -637,6 +1550,8 @@ async fn select_export_folder(app_handle: tauri::AppHandle) -> Result<String, St
     match rx.await {
         Ok(Some(path)) => {
             if let Some(path_str) = path.as_path().and_then(|p| p.to_str()) {
+                let registry = app_handle.state::<registry::FolderRegistry>();
+                registry.grant_export(Path::new(path_str))?;
                 Ok(path_str.to_string())
             } else {
                 Err("无法获取导出文件夹路径".to_string())
@@ -647,74 +1562,289 @@ async fn select_export_folder(app_handle: tauri::AppHandle) -> Result<String, St
     }
 }
 
-#[derive(Debug, Deserialize)]
-struct ExportImageRequest {
-    export_folder: String,
-    image_files: Vec<ExportImageInfo>,
-}
-
-#[derive(Debug, Deserialize)]
-struct ExportImageInfo {
-    filename: String,
-    model_paths: std::collections::HashMap<String, String>, // 模型名称到路径的映射
+// 预览导出计划（dry-run）：列出将要复制与生成的文件、重名冲突与无法导出的文件，不写入任何文件
+#[tauri::command]
+async fn preview_export(
+    registry: tauri::State<'_, registry::FolderRegistry>,
+    request: export::ExportImageRequest,
+) -> Result<export::ExportPlan, String> {
+    export::plan(&request, &registry)
 }
 
 #[tauri::command]
-async fn export_selected_images(request: ExportImageRequest) -> Result<String, String> {
+async fn export_selected_images(
+    registry: tauri::State<'_, registry::FolderRegistry>,
+    request: export::ExportImageRequest,
+) -> Result<String, String> {
     use std::fs;
-    use std::path::Path;
 
-    let export_path = Path::new(&request.export_folder);
-    if !export_path.exists() {
-        return Err("导出文件夹不存在".to_string());
+    let plan = export::plan(&request, &registry)?;
+    let unresolved = plan.unresolved();
+    if !unresolved.is_empty() {
+        let targets: Vec<&str> = unresolved.iter().map(|c| c.target.as_str()).collect();
+        return Err(format!(
+            "{} 个导出位置重名，未写入任何文件（可选择跳过、重命名或覆盖）: {}",
+            targets.len(),
+            targets.join("; ")
+        ));
     }
 
-    let mut success_count = 0;
-    let mut error_files = Vec::new();
+    let mut error_files = plan.errors.clone();
+    let mut exported = HashSet::new();
     let total_files = request.image_files.len();
 
-    for image_info in &request.image_files {
-        // 为每个图片创建子文件夹
-        let image_folder = export_path.join(&image_info.filename.replace(".", "_"));
-        if let Err(e) = fs::create_dir_all(&image_folder) {
-            error_files.push(format!("创建文件夹失败 {}: {}", image_info.filename, e));
-            continue;
+    // 导出为压缩包时文件只写入压缩文件，最后附上指标文件；先写入临时文件，完成后再重命名，失败时不留下不完整的压缩文件
+    if let (Some(options), Some(archive_path)) = (&request.archive, &plan.archive) {
+        let partial = std::path::PathBuf::from(format!("{}.partial", archive_path));
+        let written = write_archive(&request, &plan, options, &partial, &mut exported, &mut error_files)
+            .and_then(|()| {
+                fs::rename(&partial, archive_path).map_err(|e| format!("保存压缩文件失败 {}: {}", archive_path, e))
+            });
+        if let Err(e) = written {
+            let _ = fs::remove_file(&partial);
+            error_files.push(e);
+            return Err(format!("未能生成压缩文件 {}: {}", archive_path, error_files.join("; ")));
         }
+        return export_summary(exported.len(), total_files, archive_path, &error_files);
+    }
 
-        // 复制所有版本的图片到对应子文件夹
-        for (model_name, source_path) in &image_info.model_paths {
-            let source = Path::new(source_path);
-            if !source.exists() {
-                error_files.push(format!("源文件不存在: {}", source_path));
+    for operation in &plan.operations {
+        let target = Path::new(&operation.target);
+        if let Some(folder) = target.parent() {
+            if let Err(e) = fs::create_dir_all(folder) {
+                error_files.push(format!("创建文件夹失败 {}: {}", folder.display(), e));
                 continue;
             }
+        }
 
-            // 使用模型名称作为前缀，去除特殊字符
-            let safe_model_name = model_name
-                .replace("/", "_")
-                .replace("\\", "_")
-                .replace(":", "_");
-            let dest_filename = format!("{}_{}", safe_model_name, image_info.filename);
-            let dest_path = image_folder.join(dest_filename);
-
-            if let Err(e) = fs::copy(source, &dest_path) {
-                error_files.push(format!(
-                    "复制文件失败 {} -> {}: {}",
-                    source_path,
-                    dest_path.display(),
-                    e
-                ));
-                continue;
+        let result = match operation.kind {
+            export::OperationKind::Copy => fs::copy(&operation.source, target)
+                .map(|_| ())
+                .map_err(|e| format!("复制文件失败 {} -> {}: {}", operation.source, operation.target, e)),
+            export::OperationKind::Overlay => export_overlay(&request, operation)
+                .and_then(|image| image.save(target).map_err(|e| format!("保存图片失败: {}", e)))
+                .map_err(|e| format!("生成误差叠加图失败 {}: {}", operation.source, e)),
+            export::OperationKind::Contour => export_contour(&request, operation)
+                .and_then(|image| image.save(target).map_err(|e| format!("保存图片失败: {}", e)))
+                .map_err(|e| format!("生成轮廓叠加图失败 {}: {}", operation.filename, e)),
+        };
+        match result {
+            Ok(()) => {
+                exported.insert(operation.filename.as_str());
             }
+            Err(e) => error_files.push(e),
         }
+    }
+
+    export_summary(exported.len(), total_files, &request.export_folder, &error_files)
+}
+
+#[derive(Debug, Deserialize)]
+struct MetricsExportRequest {
+    results: Vec<ComparisonResult>,
+    export_folder: String,
+    // 文件名前缀，默认 metrics
+    #[serde(default)]
+    name: Option<String>,
+    // 模型的排列顺序，未列出的模型按名称排在后面
+    #[serde(default)]
+    models: Option<Vec<String>>,
+    // 运行配置（文件夹、匹配规则等），写入 xlsx 的运行配置工作表
+    #[serde(default)]
+    configuration: serde_json::Value,
+    #[serde(default)]
+    on_collision: export::CollisionPolicy,
+}
 
-        success_count += 1;
+// 将逐图指标写为长格式 CSV、宽格式 CSV 与 xlsx 工作簿，返回写入的文件路径
+#[tauri::command]
+async fn export_metrics_tables(
+    registry: tauri::State<'_, registry::FolderRegistry>,
+    request: MetricsExportRequest,
+) -> Result<Vec<String>, String> {
+    let results = &request.results;
+    let metrics = summary::present_metrics(results);
+    let models = summary::models(results, request.models.as_deref());
+    let mut configuration = vec![("exported_at".to_string(), chrono::Local::now().to_rfc3339())];
+    configuration.extend(spreadsheet::flatten_configuration(&request.configuration));
+
+    let name = request.name.as_deref().unwrap_or("metrics");
+    let files = [
+        (format!("{}_long.csv", name), spreadsheet::long_csv(results, &models, &metrics)?),
+        (format!("{}_wide.csv", name), spreadsheet::wide_csv(results, &models, &metrics)?),
+        (
+            format!("{}.xlsx", name),
+            spreadsheet::workbook(results, &models, &metrics, &configuration)?,
+        ),
+    ];
+
+    let mut written = Vec::new();
+    for (file_name, data) in files {
+        let path = Path::new(&request.export_folder).join(file_name);
+        let Some(path) = export::output_path(&registry, &path, request.on_collision)? else {
+            continue;
+        };
+        std::fs::write(&path, data).map_err(|e| format!("保存文件失败 {}: {}", path.display(), e))?;
+        written.push(path.to_string_lossy().to_string());
     }
+    Ok(written)
+}
 
+// 生成自包含的 HTML 报告并写入导出文件夹，返回写入的文件路径；按冲突策略跳过时返回空字符串
+#[tauri::command]
+async fn export_report(
+    registry: tauri::State<'_, registry::FolderRegistry>,
+    mut request: report::ReportRequest,
+) -> Result<String, String> {
+    registry.resolve_all(request.results.iter_mut().flat_map(|r| r.paths.values_mut()))?;
+    let html = report::generate(&request)?;
+
+    let name = request.name.as_deref().unwrap_or("report");
+    let path = Path::new(&request.export_folder).join(format!("{}.html", name));
+    let Some(path) = export::output_path(&registry, &path, request.on_collision)? else {
+        return Ok(String::new());
+    };
+    std::fs::write(&path, html).map_err(|e| format!("保存文件失败 {}: {}", path.display(), e))?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+// 将选中图片排成定性对比图，同时写出 PNG 与 SVG，返回写入的文件路径
+#[tauri::command]
+async fn export_figure(
+    registry: tauri::State<'_, registry::FolderRegistry>,
+    mut request: figure::FigureRequest,
+) -> Result<Vec<String>, String> {
+    registry.resolve_all(request.results.iter_mut().flat_map(|r| r.paths.values_mut()))?;
+    let layout = figure::compose(&request)?;
+
+    let name = request.name.as_deref().unwrap_or("figure");
+    let files = [
+        (format!("{}.png", name), figure::png(&layout)?),
+        (format!("{}.svg", name), figure::svg(&layout)?.into_bytes()),
+    ];
+    let mut written = Vec::new();
+    for (file_name, data) in files {
+        let path = Path::new(&request.export_folder).join(file_name);
+        let Some(path) = export::output_path(&registry, &path, request.on_collision)? else {
+            continue;
+        };
+        std::fs::write(&path, data).map_err(|e| format!("保存文件失败 {}: {}", path.display(), e))?;
+        written.push(path.to_string_lossy().to_string());
+    }
+    Ok(written)
+}
+
+// 将同一张图片的几个角色轮流显示，写为 GIF 或 APNG 动画，返回写入的文件路径；按冲突策略跳过时返回空字符串
+#[tauri::command]
+async fn export_flicker(
+    registry: tauri::State<'_, registry::FolderRegistry>,
+    mut request: flicker::FlickerRequest,
+) -> Result<String, String> {
+    registry.resolve_all(request.result.paths.values_mut())?;
+    let data = flicker::render(&request)?;
+
+    let path = Path::new(&request.export_folder).join(request.file_name());
+    let Some(path) = export::output_path(&registry, &path, request.on_collision)? else {
+        return Ok(String::new());
+    };
+    std::fs::write(&path, data).map_err(|e| format!("保存文件失败 {}: {}", path.display(), e))?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+// 生成论文用的 LaTeX 或 Markdown 结果汇总表
+#[tauri::command]
+async fn generate_results_table(
+    results: Vec<ComparisonResult>,
+    options: results_table::TableOptions,
+) -> Result<String, String> {
+    results_table::generate(&results, &options)
+}
+
+fn export_overlay(
+    request: &export::ExportImageRequest,
+    operation: &export::ExportOperation,
+) -> Result<image::DynamicImage, String> {
+    let overlay_options = request.error_overlay.as_ref();
+    render_error_overlay(
+        &operation.filename,
+        operation.gt_path.as_deref().unwrap_or_default(),
+        &operation.source,
+        operation.original_path.as_deref(),
+        overlay_options.and_then(|o| o.palette.as_ref()),
+        &overlay_options.map(|o| o.options.clone()).unwrap_or_default(),
+    )
+}
+
+fn export_contour(
+    request: &export::ExportImageRequest,
+    operation: &export::ExportOperation,
+) -> Result<image::DynamicImage, String> {
+    let contour_options = request.contour_overlay.as_ref();
+    render_contour_overlay(
+        &operation.filename,
+        operation.gt_path.as_deref().unwrap_or_default(),
+        &operation.predictions,
+        operation.original_path.as_deref(),
+        contour_options.and_then(|o| o.palette.as_ref()),
+        &contour_options.map(|o| o.options.clone()).unwrap_or_default(),
+    )
+}
+
+// 导出结果的提示信息，全部失败时返回错误
+// 将计划中的文件写入压缩文件；单个文件读取或生成失败时记入 error_files，写入压缩文件本身失败时中止
+fn write_archive<'a>(
+    request: &export::ExportImageRequest,
+    plan: &'a export::ExportPlan,
+    options: &export::ArchiveOptions,
+    path: &Path,
+    exported: &mut HashSet<&'a str>,
+    error_files: &mut Vec<String>,
+) -> Result<(), String> {
+    let mut writer = archive::ArchiveWriter::create(path, options.format)?;
+    let mut archived = Vec::new();
+    for operation in &plan.operations {
+        let data = match operation.kind {
+            export::OperationKind::Copy => std::fs::read(&operation.source)
+                .map_err(|e| format!("读取文件失败 {}: {}", operation.source, e)),
+            export::OperationKind::Overlay => export_overlay(request, operation)
+                .and_then(|image| render::png_bytes(&image))
+                .map_err(|e| format!("生成误差叠加图失败 {}: {}", operation.source, e)),
+            export::OperationKind::Contour => export_contour(request, operation)
+                .and_then(|image| render::png_bytes(&image))
+                .map_err(|e| format!("生成轮廓叠加图失败 {}: {}", operation.filename, e)),
+        };
+        match data {
+            Ok(data) => {
+                writer.add(&plan.entry_name(operation), &data)?;
+                exported.insert(operation.filename.as_str());
+                archived.push(operation.clone());
+            }
+            Err(e) => error_files.push(e),
+        }
+    }
+
+    let archived_plan = export::ExportPlan {
+        operations: archived,
+        ..plan.clone()
+    };
+    let records = export::records(&archived_plan, request);
+    let sidecar_name = plan.sidecar_name.as_deref().unwrap_or(export::SIDECAR_NAME);
+    for (name, data) in export::sidecar_files(&records, options.sidecar, sidecar_name)? {
+        writer.add(&name, &data)?;
+    }
+    writer.finish()
+}
+
+fn export_summary(
+    success_count: usize,
+    total_files: usize,
+    destination: &str,
+    error_files: &[String],
+) -> Result<String, String> {
     if error_files.is_empty() {
         Ok(format!(
             "成功导出 {} 个图片到 {}",
-            success_count, request.export_folder
+            success_count, destination
         ))
     } else {
         let error_msg = format!(
@@ -735,15 +1865,59 @@ fn main() {
     tauri::Builder::default()
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_dialog::init())
+        .setup(|app| {
+            // 用户选择过的路径保存在配置目录中，前端的文件系统权限不包含该文件
+            let store = app.path().app_config_dir()?.join("granted_folders.json");
+            app.manage(registry::FolderRegistry::load(store));
+            Ok(())
+        })
+        .on_window_event(|window, event| {
+            // 拖放到窗口上的文件夹与文件视为用户选择
+            if let tauri::WindowEvent::DragDrop(tauri::DragDropEvent::Drop { paths, .. }) = event {
+                if let Err(e) = window.state::<registry::FolderRegistry>().grant(paths) {
+                    eprintln!("记录拖放的路径失败: {}", e);
+                }
+            }
+        })
+        .register_asynchronous_uri_scheme_protocol(thumbnails::SCHEME, |ctx, request, responder| {
+            // 解码与缩放大图较慢，放到阻塞线程池中执行，避免卡住界面
+            let app = ctx.app_handle().clone();
+            tauri::async_runtime::spawn_blocking(move || {
+                let registry = app.state::<registry::FolderRegistry>();
+                let cache_dir = app.path().app_cache_dir().ok().map(|dir| dir.join("thumbnails"));
+                responder.respond(thumbnails::respond(&registry, cache_dir.as_deref(), &request));
+            });
+        })
         .invoke_handler(tauri::generate_handler![
             select_folder,
             is_file,
             get_folder_files,
+            get_annotation_mask,
+            load_palette,
+            get_volume_info,
+            get_volume_slice,
             validate_folders,
+            validate_roles,
+            ungranted_folders,
+            preflight_check,
             calculate_comparisons,
+            calculate_role_comparisons,
+            calculate_agreement,
             calculate_comparisons_with_progress,
             select_export_folder,
+            preview_export,
             export_selected_images,
+            export_metrics_tables,
+            generate_results_table,
+            export_report,
+            export_figure,
+            export_flicker,
+            get_error_overlay,
+            get_contour_overlay,
+            compute_error_heatmaps,
+            get_consensus_map,
+            get_difference_image,
+            probe_pixel,
             show_error_dialog
         ])
         .run(tauri::generate_context!())
@@ -0,0 +1,509 @@
+// 掩码加载：保留原始位深读取图片、浮点 TIFF 以及 NumPy .npy/.npz 数组
+
+use image::{DynamicImage, ImageBuffer, Luma};
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+// 掩码数值的编码方式，决定如何判定前景像素
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MaskEncoding {
+    // 8 位灰度图片，沿用原有规则：大于 128 为前景
+    Grayscale,
+    // 整数标签图（16 位图片、整数/布尔数组），非零为前景
+    Label,
+    // 浮点概率图，大于 0.5 为前景
+    Probability,
+}
+
+impl MaskEncoding {
+    pub fn threshold(self) -> f32 {
+        match self {
+            MaskEncoding::Grayscale => 128.0,
+            MaskEncoding::Label => 0.0,
+            MaskEncoding::Probability => 0.5,
+        }
+    }
+}
+
+// 以 f32 保存的单通道掩码，16 位标签与浮点概率都不会被截断
+#[derive(Debug, Clone)]
+pub struct MaskData {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<f32>,
+    pub encoding: MaskEncoding,
+}
+
+impl MaskData {
+    pub fn value(&self, x: u32, y: u32) -> f32 {
+        self.data[y as usize * self.width as usize + x as usize]
+    }
+
+    pub fn is_foreground(&self, x: u32, y: u32) -> bool {
+        self.value(x, y) > self.encoding.threshold()
+    }
+
+    // 缩放到指定尺寸：标签图使用最近邻以免产生不存在的类别，其余沿用 Lanczos3
+    pub fn resize(self, width: u32, height: u32) -> MaskData {
+        if self.width == width && self.height == height {
+            return self;
+        }
+
+        let filter = match self.encoding {
+            MaskEncoding::Label => image::imageops::FilterType::Nearest,
+            _ => image::imageops::FilterType::Lanczos3,
+        };
+        let buffer: ImageBuffer<Luma<f32>, Vec<f32>> =
+            ImageBuffer::from_raw(self.width, self.height, self.data)
+                .expect("掩码数据长度与尺寸不一致");
+        let resized = image::imageops::resize(&buffer, width, height, filter);
+
+        MaskData {
+            width,
+            height,
+            data: resized.into_raw(),
+            encoding: self.encoding,
+        }
+    }
+}
+
+// 支持作为掩码或预测结果读取的文件扩展名
+pub const MASK_EXTENSIONS: [&str; 9] = [
+    "jpg", "jpeg", "png", "bmp", "tif", "tiff", "webp", "npy", "npz",
+];
+
+// 根据扩展名加载掩码
+pub fn load_mask(path: &str) -> Result<MaskData, String> {
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "npy" => {
+            let file = File::open(path).map_err(|e| format!("无法打开文件 {}: {}", path, e))?;
+            let length = file.metadata().map_err(|e| format!("无法读取文件 {}: {}", path, e))?.len();
+            let array = read_npy(BufReader::new(file), length)
+                .map_err(|e| format!("无法解析 NumPy 数组 {}: {}", path, e))?;
+            array_to_mask(array).map_err(|e| format!("{}: {}", path, e))
+        }
+        "npz" => {
+            let array = read_npz(path).map_err(|e| format!("无法解析 NumPy 压缩包 {}: {}", path, e))?;
+            array_to_mask(array).map_err(|e| format!("{}: {}", path, e))
+        }
+        "tif" | "tiff" => load_tiff(path),
+        _ => {
+            let img = image::open(path).map_err(|e| format!("无法打开图片 {}: {}", path, e))?;
+            Ok(image_to_mask(&img))
+        }
+    }
+}
+
+// 按普通图片解码的掩码（PNG、JPEG 等）；数组、TIFF 与标注等其他来源需要专门的读取方式
+pub fn decodes_as_image(path: &str) -> bool {
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .unwrap_or_default();
+    MASK_EXTENSIONS.contains(&extension.as_str()) && !matches!(extension.as_str(), "npy" | "npz" | "tif" | "tiff")
+}
+
+// 尺寸不一致时将大的掩码压缩到小的尺寸
+pub fn align_masks(mask1: MaskData, mask2: MaskData) -> (MaskData, MaskData) {
+    let target_width = mask1.width.min(mask2.width);
+    let target_height = mask1.height.min(mask2.height);
+
+    (
+        mask1.resize(target_width, target_height),
+        mask2.resize(target_width, target_height),
+    )
+}
+
+pub fn image_to_mask(img: &DynamicImage) -> MaskData {
+    let (width, height) = (img.width(), img.height());
+    match img {
+        DynamicImage::ImageLuma16(_) | DynamicImage::ImageLumaA16(_) => {
+            let gray = img.to_luma16();
+            MaskData {
+                width,
+                height,
+                data: gray.into_raw().into_iter().map(f32::from).collect(),
+                encoding: MaskEncoding::Label,
+            }
+        }
+        DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_) => {
+            // 浮点彩色图取三通道平均值作为概率
+            let rgb = img.to_rgb32f();
+            MaskData {
+                width,
+                height,
+                data: rgb
+                    .pixels()
+                    .map(|p| (p[0] + p[1] + p[2]) / 3.0)
+                    .collect(),
+                encoding: MaskEncoding::Probability,
+            }
+        }
+        _ => {
+            let gray = img.to_luma8();
+            MaskData {
+                width,
+                height,
+                data: gray.into_raw().into_iter().map(f32::from).collect(),
+                encoding: MaskEncoding::Grayscale,
+            }
+        }
+    }
+}
+
+// image 库不支持浮点灰度 TIFF，单通道 TIFF 直接用 tiff 库解码
+fn load_tiff(path: &str) -> Result<MaskData, String> {
+    use tiff::decoder::{Decoder, DecodingResult};
+
+    let file = File::open(path).map_err(|e| format!("无法打开文件 {}: {}", path, e))?;
+    let mut decoder = Decoder::new(BufReader::new(file))
+        .map_err(|e| format!("无法解析 TIFF {}: {}", path, e))?;
+    let (width, height) = decoder
+        .dimensions()
+        .map_err(|e| format!("无法读取 TIFF 尺寸 {}: {}", path, e))?;
+    let color_type = decoder
+        .colortype()
+        .map_err(|e| format!("无法读取 TIFF 颜色类型 {}: {}", path, e))?;
+
+    // 多通道 TIFF 交给 image 库按灰度处理
+    if !matches!(color_type, tiff::ColorType::Gray(_)) {
+        let img = image::open(path).map_err(|e| format!("无法打开图片 {}: {}", path, e))?;
+        return Ok(image_to_mask(&img));
+    }
+
+    let decoded = decoder
+        .read_image()
+        .map_err(|e| format!("无法解码 TIFF {}: {}", path, e))?;
+    let (data, encoding): (Vec<f32>, MaskEncoding) = match decoded {
+        DecodingResult::U8(v) => (v.into_iter().map(f32::from).collect(), MaskEncoding::Grayscale),
+        DecodingResult::U16(v) => (v.into_iter().map(f32::from).collect(), MaskEncoding::Label),
+        DecodingResult::U32(v) => (v.into_iter().map(|x| x as f32).collect(), MaskEncoding::Label),
+        DecodingResult::U64(v) => (v.into_iter().map(|x| x as f32).collect(), MaskEncoding::Label),
+        DecodingResult::I8(v) => (v.into_iter().map(f32::from).collect(), MaskEncoding::Label),
+        DecodingResult::I16(v) => (v.into_iter().map(f32::from).collect(), MaskEncoding::Label),
+        DecodingResult::I32(v) => (v.into_iter().map(|x| x as f32).collect(), MaskEncoding::Label),
+        DecodingResult::I64(v) => (v.into_iter().map(|x| x as f32).collect(), MaskEncoding::Label),
+        DecodingResult::F32(v) => (v, MaskEncoding::Probability),
+        DecodingResult::F64(v) => (v.into_iter().map(|x| x as f32).collect(), MaskEncoding::Probability),
+    };
+
+    if data.len() as u64 != width as u64 * height as u64 {
+        return Err(format!("TIFF 数据长度与尺寸不一致: {}", path));
+    }
+
+    Ok(MaskData {
+        width,
+        height,
+        data,
+        encoding,
+    })
+}
+
+// 解析后的 NumPy 数组，按 C 顺序展开
+pub struct NpyArray {
+    pub shape: Vec<usize>,
+    pub data: Vec<f32>,
+    pub is_float: bool,
+}
+
+// 读取 .npy 格式：魔数、版本、头部字典，然后是原始数据；
+// length 为文件或压缩包条目的字节数，头部声明的大小超出时直接报错，不按头部分配内存
+pub fn read_npy<R: Read>(mut reader: R, length: u64) -> Result<NpyArray, String> {
+    let mut magic = [0u8; 8];
+    reader
+        .read_exact(&mut magic)
+        .map_err(|e| format!("读取文件头失败: {}", e))?;
+    if &magic[..6] != b"\x93NUMPY" {
+        return Err("不是有效的 .npy 文件".to_string());
+    }
+
+    let (header_len, prefix_len) = match magic[6] {
+        1 => {
+            let mut len = [0u8; 2];
+            reader
+                .read_exact(&mut len)
+                .map_err(|e| format!("读取文件头失败: {}", e))?;
+            (u16::from_le_bytes(len) as u64, 10)
+        }
+        2 | 3 => {
+            let mut len = [0u8; 4];
+            reader
+                .read_exact(&mut len)
+                .map_err(|e| format!("读取文件头失败: {}", e))?;
+            (u32::from_le_bytes(len) as u64, 12)
+        }
+        version => return Err(format!("不支持的 .npy 版本: {}", version)),
+    };
+    let remaining = length
+        .checked_sub(prefix_len + header_len)
+        .ok_or_else(|| format!("文件头长度 {} 超出文件大小", header_len))?;
+    let header_len = header_len as usize;
+
+    let mut header = vec![0u8; header_len];
+    reader
+        .read_exact(&mut header)
+        .map_err(|e| format!("读取文件头失败: {}", e))?;
+    let header = String::from_utf8_lossy(&header);
+
+    let descr = header_value(&header, "descr")
+        .map(|v| v.trim_matches(|c| c == '\'' || c == '"').to_string())
+        .ok_or("文件头缺少 descr")?;
+    let fortran_order = header_value(&header, "fortran_order")
+        .map(|v| v.starts_with("True"))
+        .unwrap_or(false);
+    let shape = parse_shape(&header)?;
+
+    let (little_endian, kind, size) = parse_descr(&descr)?;
+    let bytes = shape
+        .iter()
+        .try_fold(size, |total, &dim| total.checked_mul(dim))
+        .ok_or_else(|| format!("数组形状过大: {:?}", shape))?;
+    if bytes as u64 > remaining {
+        return Err(format!("数组数据不完整: 需要 {} 字节，只有 {} 字节", bytes, remaining));
+    }
+    let mut raw = vec![0u8; bytes];
+    reader
+        .read_exact(&mut raw)
+        .map_err(|e| format!("数组数据不完整: {}", e))?;
+
+    let data = decode_elements(&raw, kind, size, little_endian);
+    let data = if fortran_order && shape.len() > 1 {
+        fortran_to_c_order(&data, &shape)
+    } else {
+        data
+    };
+
+    Ok(NpyArray {
+        shape,
+        data,
+        is_float: kind == 'f',
+    })
+}
+
+// 读取 .npz：优先使用常见的键名，否则取第一个数组
+fn read_npz(path: &str) -> Result<NpyArray, String> {
+    let file = File::open(path).map_err(|e| format!("无法打开文件: {}", e))?;
+    let mut archive =
+        zip::ZipArchive::new(BufReader::new(file)).map_err(|e| format!("无法读取压缩包: {}", e))?;
+
+    let mut names: Vec<String> = archive
+        .file_names()
+        .filter(|n| n.ends_with(".npy"))
+        .map(|n| n.to_string())
+        .collect();
+    names.sort();
+
+    let preferred = ["mask.npy", "pred.npy", "label.npy", "arr_0.npy"];
+    let name = preferred
+        .iter()
+        .find(|p| names.iter().any(|n| n == *p))
+        .map(|p| p.to_string())
+        .or_else(|| names.first().cloned())
+        .ok_or("压缩包中没有数组")?;
+
+    let entry = archive
+        .by_name(&name)
+        .map_err(|e| format!("无法读取数组 {}: {}", name, e))?;
+    let length = entry.size();
+    read_npy(entry, length)
+}
+
+// 去掉长度为 1 的维度后必须是二维 (H, W)
+fn array_to_mask(array: NpyArray) -> Result<MaskData, String> {
+    let dims: Vec<usize> = array.shape.iter().copied().filter(|&d| d != 1).collect();
+    let (height, width) = match dims.as_slice() {
+        [h, w] => (*h, *w),
+        [] if !array.data.is_empty() => (1, 1),
+        [w] => (1, *w),
+        _ => return Err(format!("不支持的数组形状 {:?}，需要二维掩码", array.shape)),
+    };
+
+    let (Ok(width), Ok(height)) = (u32::try_from(width), u32::try_from(height)) else {
+        return Err(format!("数组尺寸过大: {:?}", array.shape));
+    };
+
+    Ok(MaskData {
+        width,
+        height,
+        data: array.data,
+        encoding: if array.is_float {
+            MaskEncoding::Probability
+        } else {
+            MaskEncoding::Label
+        },
+    })
+}
+
+// 从头部字典中取出某个键对应的原始文本
+fn header_value<'a>(header: &'a str, key: &str) -> Option<&'a str> {
+    let quoted = [format!("'{}'", key), format!("\"{}\"", key)];
+    let start = quoted.iter().find_map(|q| header.find(q.as_str()).map(|i| i + q.len()))?;
+    let rest = header[start..].trim_start().strip_prefix(':')?.trim_start();
+    let end = rest.find(',').unwrap_or(rest.len());
+    Some(rest[..end].trim())
+}
+
+fn parse_shape(header: &str) -> Result<Vec<usize>, String> {
+    let start = header.find("'shape'").or_else(|| header.find("\"shape\"")).ok_or("文件头缺少 shape")?;
+    let open = header[start..].find('(').ok_or("shape 格式错误")? + start;
+    let close = header[open..].find(')').ok_or("shape 格式错误")? + open;
+
+    header[open + 1..close]
+        .split(',')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse::<usize>().map_err(|_| format!("shape 格式错误: {}", s)))
+        .collect()
+}
+
+// descr 形如 '<f4'、'|u1'、'|b1'
+fn parse_descr(descr: &str) -> Result<(bool, char, usize), String> {
+    let mut chars = descr.chars();
+    let order = chars.next().ok_or("descr 为空")?;
+    let kind = chars.next().ok_or("descr 格式错误")?;
+    let size: usize = chars
+        .as_str()
+        .parse()
+        .map_err(|_| format!("不支持的数据类型: {}", descr))?;
+
+    if !matches!(kind, 'b' | 'u' | 'i' | 'f') || !matches!(size, 1 | 2 | 4 | 8) {
+        return Err(format!("不支持的数据类型: {}", descr));
+    }
+    if kind == 'f' && size == 1 {
+        return Err(format!("不支持的数据类型: {}", descr));
+    }
+
+    Ok((order != '>', kind, size))
+}
+
+fn decode_elements(raw: &[u8], kind: char, size: usize, little_endian: bool) -> Vec<f32> {
+    let decode = |bytes: &[u8]| -> f32 {
+        let mut buf = [0u8; 8];
+        if little_endian {
+            buf[..size].copy_from_slice(bytes);
+        } else {
+            for (i, b) in bytes.iter().rev().enumerate() {
+                buf[i] = *b;
+            }
+        }
+        match (kind, size) {
+            ('b', _) | ('u', 1) => buf[0] as f32,
+            ('i', 1) => buf[0] as i8 as f32,
+            ('u', 2) => u16::from_le_bytes([buf[0], buf[1]]) as f32,
+            ('i', 2) => i16::from_le_bytes([buf[0], buf[1]]) as f32,
+            ('u', 4) => u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f32,
+            ('i', 4) => i32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f32,
+            ('f', 2) => half_to_f32(u16::from_le_bytes([buf[0], buf[1]])),
+            ('f', 4) => f32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]),
+            ('u', 8) => u64::from_le_bytes(buf) as f32,
+            ('i', 8) => i64::from_le_bytes(buf) as f32,
+            ('f', 8) => f64::from_le_bytes(buf) as f32,
+            _ => 0.0,
+        }
+    };
+
+    raw.chunks_exact(size).map(decode).collect()
+}
+
+// IEEE 754 半精度转单精度
+fn half_to_f32(bits: u16) -> f32 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((bits >> 10) & 0x1f) as i32;
+    let mantissa = (bits & 0x3ff) as f32;
+
+    match exponent {
+        0 => sign * mantissa * 2f32.powi(-24),
+        31 if mantissa == 0.0 => sign * f32::INFINITY,
+        31 => f32::NAN,
+        _ => sign * (1.0 + mantissa / 1024.0) * 2f32.powi(exponent - 15),
+    }
+}
+
+// Fortran 顺序（列优先）转为 C 顺序（行优先）
+fn fortran_to_c_order(data: &[f32], shape: &[usize]) -> Vec<f32> {
+    let count = data.len();
+    let mut result = vec![0.0; count];
+    let mut index = vec![0usize; shape.len()];
+
+    for value in result.iter_mut() {
+        // 当前 C 顺序下标在 Fortran 存储中的偏移
+        let mut offset = 0;
+        let mut stride = 1;
+        for (i, &dim) in shape.iter().enumerate() {
+            offset += index[i] * stride;
+            stride *= dim;
+        }
+        *value = data[offset];
+
+        for i in (0..shape.len()).rev() {
+            index[i] += 1;
+            if index[i] < shape[i] {
+                break;
+            }
+            index[i] = 0;
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 按 .npy 1.0 格式拼出文件：魔数、版本、头部长度、头部字典、数据
+    fn npy(header: &str, data: &[u8]) -> Vec<u8> {
+        let mut bytes = b"\x93NUMPY\x01\x00".to_vec();
+        bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(header.as_bytes());
+        bytes.extend_from_slice(data);
+        bytes
+    }
+
+    fn read(bytes: &[u8]) -> Result<NpyArray, String> {
+        read_npy(bytes, bytes.len() as u64)
+    }
+
+    #[test]
+    fn reads_small_array() {
+        let bytes = npy("{'descr': '|u1', 'fortran_order': False, 'shape': (2, 3), }", &[0, 1, 2, 3, 4, 5]);
+        let array = read(&bytes).unwrap();
+        assert_eq!(array.shape, [2, 3]);
+        assert_eq!(array.data, [0.0, 1.0, 2.0, 3.0, 4.0, 5.0]);
+
+        let mask = array_to_mask(array).unwrap();
+        assert_eq!((mask.width, mask.height), (3, 2));
+        assert_eq!(mask.value(2, 1), 5.0);
+    }
+
+    #[test]
+    fn rejects_truncated_data() {
+        let bytes = npy("{'descr': '<f4', 'fortran_order': False, 'shape': (4, 4), }", &[0; 8]);
+        assert!(read(&bytes).is_err());
+    }
+
+    #[test]
+    fn rejects_oversized_shape() {
+        let header = format!("{{'descr': '<f8', 'fortran_order': False, 'shape': ({0}, {0}), }}", usize::MAX / 2);
+        assert!(read(&npy(&header, &[])).is_err());
+
+        let header = "{'descr': '|u1', 'fortran_order': False, 'shape': (100000, 100000), }";
+        assert!(read(&npy(header, &[0; 16])).is_err());
+    }
+
+    #[test]
+    fn rejects_header_longer_than_file() {
+        let mut bytes = b"\x93NUMPY\x02\x00".to_vec();
+        bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+        bytes.extend_from_slice(b"{'descr': '|u1'");
+        assert!(read(&bytes).is_err());
+    }
+}
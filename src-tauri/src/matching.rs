@@ -0,0 +1,151 @@
+// 跨文件夹的文件名匹配规则：完全一致、忽略扩展名按主文件名匹配、去除前后缀，或用正则的捕获组作为键
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchMode {
+    // 文件名完全一致（原有行为）
+    #[default]
+    Exact,
+    // 忽略扩展名，按主文件名匹配
+    Stem,
+    // 用正则表达式的第一个捕获组（或名为 key 的捕获组）作为键
+    Regex,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MatchRule {
+    #[serde(default)]
+    pub mode: MatchMode,
+    // 从主文件名中去除的前缀与后缀，例如 "_pred"
+    #[serde(default)]
+    pub strip_prefixes: Vec<String>,
+    #[serde(default)]
+    pub strip_suffixes: Vec<String>,
+    #[serde(default)]
+    pub pattern: Option<String>,
+}
+
+// 编译后的匹配规则
+pub struct Matcher {
+    rule: MatchRule,
+    regex: Option<Regex>,
+}
+
+impl Matcher {
+    pub fn new(rule: MatchRule) -> Result<Matcher, String> {
+        let regex = match rule.mode {
+            MatchMode::Regex => {
+                let pattern = rule.pattern.as_deref().ok_or("正则匹配模式需要提供表达式")?;
+                let regex = Regex::new(pattern).map_err(|e| format!("正则表达式无效: {}", e))?;
+                if regex.captures_len() < 2 {
+                    return Err("正则表达式需要至少一个捕获组作为匹配键".to_string());
+                }
+                Some(regex)
+            }
+            _ => None,
+        };
+        Ok(Matcher { rule, regex })
+    }
+
+    // 文件的匹配键；正则不匹配时返回 None，该文件不参与对比
+    pub fn key(&self, filename: &str) -> Option<String> {
+        match self.rule.mode {
+            MatchMode::Regex => {
+                let captures = self.regex.as_ref()?.captures(filename)?;
+                captures
+                    .name("key")
+                    .or_else(|| captures.get(1))
+                    .map(|m| m.as_str().to_string())
+            }
+            // 递归扫描时只处理最后一级文件名，保留所在的子文件夹
+            MatchMode::Exact | MatchMode::Stem => {
+                let (folder, name) = match filename.rsplit_once('/') {
+                    Some((folder, name)) => (format!("{}/", folder), name),
+                    None => (String::new(), filename),
+                };
+                let (stem, extension) = split_extension(name);
+                let extension = if self.rule.mode == MatchMode::Exact { extension } else { "" };
+                Some(format!("{}{}{}", folder, self.strip(stem), extension))
+            }
+        }
+    }
+
+    fn strip<'a>(&self, stem: &'a str) -> &'a str {
+        let mut stem = stem;
+        if let Some(stripped) = self.rule.strip_prefixes.iter().find_map(|p| stem.strip_prefix(p.as_str())) {
+            stem = stripped;
+        }
+        if let Some(stripped) = self.rule.strip_suffixes.iter().find_map(|s| stem.strip_suffix(s.as_str())) {
+            stem = stripped;
+        }
+        stem
+    }
+
+    // 建立 键 -> 实际文件名 的索引；多个文件得到同一个键时保留排序靠前的一个
+    pub fn index(&self, files: &[String]) -> HashMap<String, String> {
+        let mut index = HashMap::new();
+        for file in files {
+            let Some(key) = self.key(file) else {
+                continue;
+            };
+            if let Some(existing) = index.get(&key) {
+                eprintln!("文件名匹配冲突: {} 与 {} 的匹配键均为 {}", existing, file, key);
+                continue;
+            }
+            index.insert(key, file.clone());
+        }
+        index
+    }
+}
+
+// 拆分主文件名与扩展名，.nii.gz 视为一个扩展名
+pub fn split_extension(filename: &str) -> (&str, &str) {
+    if filename.to_lowercase().ends_with(".nii.gz") {
+        let split = filename.len() - ".nii.gz".len();
+        return filename.split_at(split);
+    }
+    match filename.rfind('.') {
+        Some(0) | None => (filename, ""),
+        Some(split) => filename.split_at(split),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stem_mode_strips_suffix_and_keeps_folder() {
+        let matcher = Matcher::new(MatchRule {
+            mode: MatchMode::Stem,
+            strip_suffixes: vec!["_pred".to_string()],
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(matcher.key("case/001_pred.png").as_deref(), Some("case/001"));
+        assert_eq!(matcher.key("001.nii.gz").as_deref(), Some("001"));
+    }
+
+    #[test]
+    fn regex_mode_uses_capture_group() {
+        let rule = |pattern: &str| MatchRule {
+            mode: MatchMode::Regex,
+            pattern: Some(pattern.to_string()),
+            ..Default::default()
+        };
+        let matcher = Matcher::new(rule(r"img_(\d+)")).unwrap();
+        assert_eq!(matcher.key("img_042_mask.png").as_deref(), Some("042"));
+        assert_eq!(matcher.key("other.png"), None);
+        assert!(Matcher::new(rule(r"img_\d+")).is_err());
+    }
+
+    #[test]
+    fn splits_compound_extension() {
+        assert_eq!(split_extension("a.NII.GZ"), ("a", ".NII.GZ"));
+        assert_eq!(split_extension(".hidden"), (".hidden", ""));
+    }
+}
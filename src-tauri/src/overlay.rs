@@ -0,0 +1,146 @@
+// 误差叠加图：按 GT 与预测的混淆情况为像素着色（TP、FP、FN），可叠加在原始图片上
+
+use crate::mask::MaskData;
+use image::imageops::FilterType;
+use image::{DynamicImage, Rgb, RgbImage};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OverlayOptions {
+    #[serde(default = "default_tp_color")]
+    pub tp_color: [u8; 3],
+    #[serde(default = "default_fp_color")]
+    pub fp_color: [u8; 3],
+    #[serde(default = "default_fn_color")]
+    pub fn_color: [u8; 3],
+    // 叠加在原始图片上时颜色的不透明度，0 到 1
+    #[serde(default = "default_opacity")]
+    pub opacity: f32,
+}
+
+fn default_tp_color() -> [u8; 3] {
+    [0, 200, 0]
+}
+
+fn default_fp_color() -> [u8; 3] {
+    [230, 0, 0]
+}
+
+fn default_fn_color() -> [u8; 3] {
+    [0, 100, 255]
+}
+
+fn default_opacity() -> f32 {
+    0.5
+}
+
+impl Default for OverlayOptions {
+    fn default() -> Self {
+        OverlayOptions {
+            tp_color: default_tp_color(),
+            fp_color: default_fp_color(),
+            fn_color: default_fn_color(),
+            opacity: default_opacity(),
+        }
+    }
+}
+
+// 两个角色之间的差异图：只有 A 为前景、只有 B 为前景、两者均为前景分别着色
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DifferenceOptions {
+    #[serde(default = "default_a_only_color")]
+    pub a_only_color: [u8; 3],
+    #[serde(default = "default_b_only_color")]
+    pub b_only_color: [u8; 3],
+    #[serde(default = "default_both_color")]
+    pub both_color: [u8; 3],
+    #[serde(default = "default_opacity")]
+    pub opacity: f32,
+}
+
+fn default_a_only_color() -> [u8; 3] {
+    [255, 140, 0]
+}
+
+fn default_b_only_color() -> [u8; 3] {
+    [200, 0, 200]
+}
+
+fn default_both_color() -> [u8; 3] {
+    [128, 128, 128]
+}
+
+impl Default for DifferenceOptions {
+    fn default() -> Self {
+        DifferenceOptions {
+            a_only_color: default_a_only_color(),
+            b_only_color: default_b_only_color(),
+            both_color: default_both_color(),
+            opacity: default_opacity(),
+        }
+    }
+}
+
+impl DifferenceOptions {
+    // 以 A 作为 GT、B 作为预测时，FN 即只有 A、FP 即只有 B
+    pub fn as_overlay(&self) -> OverlayOptions {
+        OverlayOptions {
+            tp_color: self.both_color,
+            fp_color: self.b_only_color,
+            fn_color: self.a_only_color,
+            opacity: self.opacity,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DifferenceImage {
+    pub image: String,
+    pub differing_pixels: usize,
+    pub total_pixels: usize,
+}
+
+// 两个掩码前景不一致的像素数，两者需尺寸一致
+pub fn count_differences(a: &MaskData, b: &MaskData) -> usize {
+    (0..a.height)
+        .flat_map(|y| (0..a.width).map(move |x| (x, y)))
+        .filter(|&(x, y)| a.is_foreground(x, y) != b.is_foreground(x, y))
+        .count()
+}
+
+// GT 与预测需尺寸一致；提供原始图片时缩放到掩码尺寸后作为底图，TN 像素显示原图，否则为黑色
+pub fn render_error_overlay(
+    gt: &MaskData,
+    pred: &MaskData,
+    original: Option<&DynamicImage>,
+    options: &OverlayOptions,
+) -> DynamicImage {
+    let background = original.map(|img| {
+        if img.width() != gt.width || img.height() != gt.height {
+            img.resize_exact(gt.width, gt.height, FilterType::Triangle).to_rgb8()
+        } else {
+            img.to_rgb8()
+        }
+    });
+    let opacity = options.opacity.clamp(0.0, 1.0);
+
+    let image = RgbImage::from_fn(gt.width, gt.height, |x, y| {
+        let base = background.as_ref().map(|b| *b.get_pixel(x, y));
+        let color = match (gt.is_foreground(x, y), pred.is_foreground(x, y)) {
+            (true, true) => options.tp_color,
+            (false, true) => options.fp_color,
+            (true, false) => options.fn_color,
+            (false, false) => return base.unwrap_or(Rgb([0, 0, 0])),
+        };
+        match base {
+            Some(base) => Rgb(blend(base.0, color, opacity)),
+            None => Rgb(color),
+        }
+    });
+    DynamicImage::ImageRgb8(image)
+}
+
+pub fn blend(base: [u8; 3], color: [u8; 3], opacity: f32) -> [u8; 3] {
+    let mix = |b: u8, c: u8| (b as f32 * (1.0 - opacity) + c as f32 * opacity).round() as u8;
+    [mix(base[0], color[0]), mix(base[1], color[1]), mix(base[2], color[2])]
+}
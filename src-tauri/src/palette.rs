@@ -0,0 +1,241 @@
+// 调色板掩码：将 RGB 颜色编码的标签（Pascal VOC、Cityscapes 等）解码为类别 ID
+
+use crate::mask::{self, MaskData, MaskEncoding};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::Path;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaletteEntry {
+    pub color: [u8; 3],
+    pub class_id: u32,
+    pub name: String,
+}
+
+// 颜色 -> 类别 ID -> 类别名称；类别 0 视为背景
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Palette {
+    pub entries: Vec<PaletteEntry>,
+}
+
+// 单个类别的指标
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClassScore {
+    pub class_id: u32,
+    pub name: String,
+    pub iou: f64,
+    pub dice: f64,
+}
+
+impl Palette {
+    // 从文件加载：PNG 读取内嵌调色板，JSON 读取条目列表，其余按文本格式解析
+    pub fn load(path: &str) -> Result<Palette, String> {
+        let extension = Path::new(path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase())
+            .unwrap_or_default();
+
+        let palette = match extension.as_str() {
+            "png" => Palette::from_indexed_png(path)?,
+            "json" => {
+                let content = fs::read_to_string(path).map_err(|e| format!("无法读取调色板 {}: {}", path, e))?;
+                serde_json::from_str::<Palette>(&content)
+                    .or_else(|_| serde_json::from_str::<Vec<PaletteEntry>>(&content).map(|entries| Palette { entries }))
+                    .map_err(|e| format!("无法解析调色板 {}: {}", path, e))?
+            }
+            _ => {
+                let content = fs::read_to_string(path).map_err(|e| format!("无法读取调色板 {}: {}", path, e))?;
+                Palette::parse_text(&content).map_err(|e| format!("无法解析调色板 {}: {}", path, e))?
+            }
+        };
+
+        if palette.entries.is_empty() {
+            return Err(format!("调色板为空: {}", path));
+        }
+        Ok(palette)
+    }
+
+    // 索引 PNG 的 PLTE 块：第 i 个颜色对应类别 i
+    fn from_indexed_png(path: &str) -> Result<Palette, String> {
+        let file = File::open(path).map_err(|e| format!("无法打开文件 {}: {}", path, e))?;
+        let reader = png::Decoder::new(BufReader::new(file))
+            .read_info()
+            .map_err(|e| format!("无法解析 PNG {}: {}", path, e))?;
+        let colors = reader
+            .info()
+            .palette
+            .as_ref()
+            .ok_or_else(|| format!("PNG 不是索引图片，没有内嵌调色板: {}", path))?;
+
+        let entries = colors
+            .chunks_exact(3)
+            .enumerate()
+            .map(|(index, rgb)| PaletteEntry {
+                color: [rgb[0], rgb[1], rgb[2]],
+                class_id: index as u32,
+                name: format!("类别 {}", index),
+            })
+            .collect();
+
+        Ok(Palette { entries })
+    }
+
+    // 每行 `r,g,b,class_id[,name]`；也兼容 CVAT 的 labelmap.txt（`name:r,g,b::`，类别 ID 为行序号）
+    fn parse_text(content: &str) -> Result<Palette, String> {
+        let mut entries = Vec::new();
+
+        for line in content.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if line.contains(':') {
+                let mut parts = line.split(':');
+                let name = parts.next().unwrap_or_default().trim().to_string();
+                let color = parse_color(parts.next().unwrap_or_default())?;
+                entries.push(PaletteEntry {
+                    color,
+                    class_id: entries.len() as u32,
+                    name,
+                });
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            if fields.len() < 4 {
+                return Err(format!("格式错误，应为 r,g,b,class_id[,name]: {}", line));
+            }
+            let color = parse_color(&fields[..3].join(","))?;
+            let class_id = fields[3]
+                .parse::<u32>()
+                .map_err(|_| format!("类别 ID 无效: {}", line))?;
+            let name = fields
+                .get(4)
+                .map(|n| n.to_string())
+                .unwrap_or_else(|| format!("类别 {}", class_id));
+            entries.push(PaletteEntry { color, class_id, name });
+        }
+
+        Ok(Palette { entries })
+    }
+
+    pub fn class_name(&self, class_id: u32) -> String {
+        self.entries
+            .iter()
+            .find(|e| e.class_id == class_id)
+            .map(|e| e.name.clone())
+            .unwrap_or_else(|| format!("类别 {}", class_id))
+    }
+
+    // 按调色板加载掩码：图片逐像素映射到类别 ID，数组与体数据本身已是类别 ID，按原方式读取
+    pub fn load_mask(&self, path: &str) -> Result<MaskData, String> {
+        let extension = Path::new(path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase())
+            .unwrap_or_default();
+        if matches!(extension.as_str(), "npy" | "npz") {
+            return mask::load_mask(path);
+        }
+
+        let img = image::open(path).map_err(|e| format!("无法打开图片 {}: {}", path, e))?;
+        Ok(self.image_to_mask(&img))
+    }
+
+    // 已解码的图片逐像素映射到类别 ID
+    pub fn image_to_mask(&self, img: &image::DynamicImage) -> MaskData {
+        let img = img.to_rgb8();
+
+        // 颜色到类别的映射；JPEG 等有损格式中不在调色板内的颜色取最近的条目
+        let mut lookup: HashMap<[u8; 3], u32> = HashMap::new();
+        for entry in self.entries.iter().rev() {
+            lookup.insert(entry.color, entry.class_id);
+        }
+
+        let (width, height) = img.dimensions();
+        let data = img
+            .pixels()
+            .map(|p| {
+                let color = p.0;
+                let class_id = match lookup.get(&color) {
+                    Some(&id) => id,
+                    None => {
+                        let id = self.nearest(color);
+                        lookup.insert(color, id);
+                        id
+                    }
+                };
+                class_id as f32
+            })
+            .collect();
+
+        MaskData {
+            width,
+            height,
+            data,
+            encoding: MaskEncoding::Label,
+        }
+    }
+
+    fn nearest(&self, color: [u8; 3]) -> u32 {
+        self.entries
+            .iter()
+            .min_by_key(|e| {
+                e.color
+                    .iter()
+                    .zip(color.iter())
+                    .map(|(&a, &b)| (a as i32 - b as i32).pow(2))
+                    .sum::<i32>()
+            })
+            .map(|e| e.class_id)
+            .unwrap_or(0)
+    }
+
+    // 逐类别的 IOU 与 Dice，只统计 GT 或预测中出现的非背景类别；两者尺寸需一致
+    pub fn class_scores(&self, gt: &MaskData, pred: &MaskData) -> Vec<ClassScore> {
+        // 类别 -> (交集, GT 像素数, 预测像素数)
+        let mut counts: HashMap<u32, (u64, u64, u64)> = HashMap::new();
+        for (&g, &p) in gt.data.iter().zip(pred.data.iter()) {
+            let (g, p) = (g as u32, p as u32);
+            if g != 0 {
+                let entry = counts.entry(g).or_default();
+                entry.1 += 1;
+                if g == p {
+                    entry.0 += 1;
+                }
+            }
+            if p != 0 {
+                counts.entry(p).or_default().2 += 1;
+            }
+        }
+
+        let mut scores: Vec<ClassScore> = counts
+            .into_iter()
+            .map(|(class_id, (intersection, gt_count, pred_count))| {
+                let union = gt_count + pred_count - intersection;
+                ClassScore {
+                    class_id,
+                    name: self.class_name(class_id),
+                    iou: intersection as f64 / union as f64,
+                    dice: 2.0 * intersection as f64 / (gt_count + pred_count) as f64,
+                }
+            })
+            .collect();
+        scores.sort_by_key(|s| s.class_id);
+        scores
+    }
+}
+
+fn parse_color(text: &str) -> Result<[u8; 3], String> {
+    let values: Vec<u8> = text
+        .split(',')
+        .map(|v| v.trim().parse::<u8>().map_err(|_| format!("颜色值无效: {}", text)))
+        .collect::<Result<_, _>>()?;
+    match values.as_slice() {
+        [r, g, b] => Ok([*r, *g, *b]),
+        _ => Err(format!("颜色应为 r,g,b: {}", text)),
+    }
+}
@@ -0,0 +1,297 @@
+// 深度预检：并行读取文件头或完整解码，检查可读性、与 GT 的尺寸是否一致、通道数与掩码取值，给出每个文件夹的就绪结论
+
+use crate::mask::{self, MaskData, MaskEncoding};
+use crate::palette::Palette;
+use crate::volume;
+use image::codecs::bmp::BmpDecoder;
+use image::codecs::dds::DdsDecoder;
+use image::codecs::farbfeld::FarbfeldDecoder;
+use image::codecs::gif::GifDecoder;
+use image::codecs::hdr::HdrAdapter;
+use image::codecs::ico::IcoDecoder;
+use image::codecs::jpeg::JpegDecoder;
+use image::codecs::openexr::OpenExrDecoder;
+use image::codecs::png::PngDecoder;
+use image::codecs::pnm::PnmDecoder;
+use image::codecs::qoi::QoiDecoder;
+use image::codecs::tga::TgaDecoder;
+use image::codecs::webp::WebPDecoder;
+use image::{ImageDecoder, ImageFormat, ImageResult};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::File;
+use std::io::BufReader;
+
+// 参与预检的文件夹：角色名称、路径与 匹配键 -> 实际文件名 的索引；原始图片不是掩码，不检查取值
+pub struct FolderInput {
+    pub role: String,
+    pub folder: String,
+    pub files: HashMap<String, String>,
+    pub is_mask: bool,
+}
+
+// 单个文件的检查结果；仅读取文件头时不统计取值
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileCheck {
+    pub file: String,
+    // 图片为 [宽, 高]，体数据为 [x, y, z]
+    pub dims: Vec<usize>,
+    pub channels: Option<u8>,
+    pub unique_values: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileIssue {
+    pub file: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Readiness {
+    // 可以直接对比
+    Ready,
+    // 可以对比，但结果可能受影响（尺寸需要缩放、掩码不是二值等）
+    Warning,
+    // 存在无法读取的文件，这些文件的指标会是 0
+    Blocked,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FolderReport {
+    pub role: String,
+    pub checked: usize,
+    pub unreadable: Vec<FileIssue>,
+    pub size_mismatches: Vec<FileIssue>,
+    // 通道数 -> 文件数
+    pub channel_counts: BTreeMap<u8, usize>,
+    // 取值超过两种的二值掩码（灰度编码），常见于 JPEG 压缩或抗锯齿
+    pub non_binary: Vec<FileIssue>,
+    pub files: Vec<FileCheck>,
+    pub readiness: Readiness,
+    pub reasons: Vec<String>,
+}
+
+// 检查所有文件夹中的 keys 对应的文件；reference 为尺寸基准文件夹（通常是 GT）的下标
+pub fn check_folders(
+    folders: &[FolderInput],
+    reference: usize,
+    keys: &[String],
+    palette: Option<&Palette>,
+    full_decode: bool,
+) -> Vec<FolderReport> {
+    let checks: Vec<Vec<(String, Inspection)>> = folders
+        .iter()
+        .map(|input| {
+            keys.par_iter()
+                .map(|key| {
+                    let file = input.files.get(key).map(String::as_str).unwrap_or(key);
+                    let path = format!("{}/{}", input.folder, file);
+                    let palette = palette.filter(|_| input.is_mask);
+                    (key.clone(), inspect(&path, file, palette, full_decode))
+                })
+                .collect()
+        })
+        .collect();
+
+    // 基准文件夹中每个键的尺寸
+    let reference_dims: HashMap<String, Vec<usize>> = checks
+        .get(reference)
+        .map(|checks| {
+            checks
+                .iter()
+                .filter_map(|(key, check)| check.as_ref().ok().map(|(c, _)| (key.clone(), c.dims.clone())))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    folders
+        .iter()
+        .zip(checks)
+        .enumerate()
+        .map(|(index, (input, checks))| {
+            let mut report = FolderReport {
+                role: input.role.clone(),
+                checked: checks.len(),
+                unreadable: Vec::new(),
+                size_mismatches: Vec::new(),
+                channel_counts: BTreeMap::new(),
+                non_binary: Vec::new(),
+                files: Vec::new(),
+                readiness: Readiness::Ready,
+                reasons: Vec::new(),
+            };
+
+            for (key, check) in checks {
+                let (check, encoding) = match check {
+                    Ok(check) => check,
+                    Err(message) => {
+                        let file = input.files.get(&key).cloned().unwrap_or(key);
+                        report.unreadable.push(FileIssue { file, message });
+                        continue;
+                    }
+                };
+
+                if index != reference {
+                    if let Some(expected) = reference_dims.get(key.as_str()) {
+                        if *expected != check.dims {
+                            report.size_mismatches.push(FileIssue {
+                                file: check.file.clone(),
+                                message: format!(
+                                    "尺寸 {} 与 {} 的 {} 不一致",
+                                    format_dims(&check.dims),
+                                    folders[reference].role,
+                                    format_dims(expected)
+                                ),
+                            });
+                        }
+                    }
+                }
+                if let Some(channels) = check.channels {
+                    *report.channel_counts.entry(channels).or_default() += 1;
+                }
+                if let (true, Some(count), Some(MaskEncoding::Grayscale)) = (input.is_mask, check.unique_values, encoding) {
+                    if count > 2 {
+                        report.non_binary.push(FileIssue {
+                            file: check.file.clone(),
+                            message: format!("包含 {} 种取值，将按阈值二值化", count),
+                        });
+                    }
+                }
+                report.files.push(check);
+            }
+
+            if !report.unreadable.is_empty() {
+                report.readiness = Readiness::Blocked;
+                report.reasons.push(format!("{} 个文件无法读取", report.unreadable.len()));
+            }
+            if !report.size_mismatches.is_empty() {
+                report.reasons.push(format!("{} 个文件尺寸与基准不一致，计算时会缩放", report.size_mismatches.len()));
+            }
+            if report.channel_counts.len() > 1 {
+                report.reasons.push("文件的通道数不一致".to_string());
+            }
+            if !report.non_binary.is_empty() {
+                report.reasons.push(format!("{} 个掩码不是二值图", report.non_binary.len()));
+            }
+            if report.readiness == Readiness::Ready && !report.reasons.is_empty() {
+                report.readiness = Readiness::Warning;
+            }
+            report
+        })
+        .collect()
+}
+
+// 单个文件的检查结果与掩码编码（仅读取文件头时编码未知）
+type Inspection = Result<(FileCheck, Option<MaskEncoding>), String>;
+
+// 读取单个文件；图片在仅读取文件头时不解码像素，数组与体数据没有单独的文件头读取，总是完整加载
+fn inspect(
+    path: &str,
+    file: &str,
+    palette: Option<&Palette>,
+    full_decode: bool,
+) -> Inspection {
+    if volume::is_volume_file(file) {
+        let volume = volume::load_volume(path)?;
+        let unique_values = full_decode.then(|| count_unique(&volume.data));
+        let check = FileCheck {
+            file: file.to_string(),
+            dims: volume.dims.to_vec(),
+            channels: Some(1),
+            unique_values,
+        };
+        return Ok((check, Some(volume.encoding)));
+    }
+
+    let format = ImageFormat::from_path(path).ok();
+    let channels = match format {
+        Some(format) => channel_count(path, format)?,
+        None => None,
+    };
+
+    if !full_decode && format.is_some() {
+        let (width, height) = match format {
+            Some(ImageFormat::Tiff) => tiff_dimensions(path)?,
+            _ => image::image_dimensions(path).map_err(|e| format!("无法读取图片 {}: {}", path, e))?,
+        };
+        let check = FileCheck {
+            file: file.to_string(),
+            dims: vec![width as usize, height as usize],
+            channels,
+            unique_values: None,
+        };
+        return Ok((check, None));
+    }
+
+    let mask: MaskData = match palette {
+        Some(palette) => palette.load_mask(path)?,
+        None => mask::load_mask(path)?,
+    };
+    let check = FileCheck {
+        file: file.to_string(),
+        dims: vec![mask.width as usize, mask.height as usize],
+        channels,
+        unique_values: full_decode.then(|| count_unique(&mask.data)),
+    };
+    Ok((check, Some(mask.encoding)))
+}
+
+// 从文件头读取通道数，不解码像素；无法只读取文件头的格式返回 None
+fn channel_count(path: &str, format: ImageFormat) -> Result<Option<u8>, String> {
+    let open = || {
+        File::open(path)
+            .map(BufReader::new)
+            .map_err(|e| format!("无法打开文件 {}: {}", path, e))
+    };
+    fn channels<'a>(decoder: ImageResult<impl ImageDecoder<'a>>, path: &str) -> Result<Option<u8>, String> {
+        decoder
+            .map(|decoder| Some(decoder.color_type().channel_count()))
+            .map_err(|e| format!("无法读取图片 {}: {}", path, e))
+    }
+
+    match format {
+        ImageFormat::Png => channels(PngDecoder::new(open()?), path),
+        ImageFormat::Jpeg => channels(JpegDecoder::new(open()?), path),
+        ImageFormat::Bmp => channels(BmpDecoder::new(open()?), path),
+        ImageFormat::Gif => channels(GifDecoder::new(open()?), path),
+        ImageFormat::WebP => channels(WebPDecoder::new(open()?), path),
+        ImageFormat::Tga => channels(TgaDecoder::new(open()?), path),
+        ImageFormat::Ico => channels(IcoDecoder::new(open()?), path),
+        ImageFormat::Pnm => channels(PnmDecoder::new(open()?), path),
+        ImageFormat::Qoi => channels(QoiDecoder::new(open()?), path),
+        ImageFormat::Hdr => channels(HdrAdapter::new(open()?), path),
+        ImageFormat::OpenExr => channels(OpenExrDecoder::new(open()?), path),
+        ImageFormat::Farbfeld => channels(FarbfeldDecoder::new(open()?), path),
+        ImageFormat::Dds => channels(DdsDecoder::new(open()?), path),
+        ImageFormat::Tiff => {
+            let mut decoder =
+                tiff::decoder::Decoder::new(open()?).map_err(|e| format!("无法读取 TIFF {}: {}", path, e))?;
+            let color = decoder.colortype().map_err(|e| format!("无法读取 TIFF {}: {}", path, e))?;
+            Ok(Some(match color {
+                tiff::ColorType::Gray(_) | tiff::ColorType::Palette(_) => 1,
+                tiff::ColorType::GrayA(_) => 2,
+                tiff::ColorType::RGB(_) | tiff::ColorType::YCbCr(_) => 3,
+                tiff::ColorType::RGBA(_) | tiff::ColorType::CMYK(_) => 4,
+            }))
+        }
+        _ => Ok(None),
+    }
+}
+
+// image 不支持浮点 TIFF，尺寸直接从 TIFF 文件头读取
+fn tiff_dimensions(path: &str) -> Result<(u32, u32), String> {
+    let file = File::open(path).map_err(|e| format!("无法打开文件 {}: {}", path, e))?;
+    tiff::decoder::Decoder::new(BufReader::new(file))
+        .and_then(|mut decoder| decoder.dimensions())
+        .map_err(|e| format!("无法读取 TIFF {}: {}", path, e))
+}
+
+fn count_unique(data: &[f32]) -> usize {
+    data.iter().map(|v| v.to_bits()).collect::<HashSet<u32>>().len()
+}
+
+fn format_dims(dims: &[usize]) -> String {
+    dims.iter().map(|d| d.to_string()).collect::<Vec<_>>().join("x")
+}
@@ -0,0 +1,51 @@
+// 像素探针：读取各角色文件在某个坐标上的原始值，供界面悬停查看
+
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PixelProbe {
+    pub role: String,
+    // 文件自身坐标系中的位置，坐标按参考尺寸换算
+    pub x: u32,
+    pub y: u32,
+    // 原始通道值（灰度 1 个、RGB 3 个等）；数组与浮点 TIFF 等无法按图片读取时为掩码值
+    pub raw: Vec<f32>,
+    // 按掩码编码读取的值与二值化结果
+    pub value: Option<f32>,
+    pub foreground: Option<bool>,
+    // 提供调色板时的类别
+    pub class_id: Option<u32>,
+    pub class_name: Option<String>,
+    pub error: Option<String>,
+}
+
+// 将参考尺寸中的坐标按比例换算到目标尺寸；坐标在参考尺寸（未提供时为目标尺寸）之外时报错，不取边缘像素代替
+pub fn scale_point(x: u32, y: u32, reference: Option<[u32; 2]>, width: u32, height: u32) -> Result<(u32, u32), String> {
+    let [from_width, from_height] = reference.unwrap_or([width, height]);
+    if x >= from_width || y >= from_height || width == 0 || height == 0 {
+        return Err(format!("坐标越界: ({}, {})，图片尺寸 {}x{}", x, y, from_width, from_height));
+    }
+    let scale = |v: u32, from: u32, to: u32| (v as u64 * to as u64 / from as u64) as u32;
+    Ok((scale(x, from_width, width), scale(y, from_height, height)))
+}
+
+// 按像素格式读取原始通道值，8 位、16 位与 32 位浮点分别按 u8、u16、f32 解释
+pub fn raw_pixel(image: &DynamicImage, x: u32, y: u32) -> Vec<f32> {
+    let color = image.color();
+    let channels = color.channel_count() as usize;
+    let bytes_per_pixel = color.bytes_per_pixel() as usize;
+    let bytes_per_channel = bytes_per_pixel / channels.max(1);
+    let offset = (y as usize * image.width() as usize + x as usize) * bytes_per_pixel;
+    let bytes = &image.as_bytes()[offset..offset + bytes_per_pixel];
+
+    bytes
+        .chunks_exact(bytes_per_channel)
+        .map(|b| match b {
+            [v] => *v as f32,
+            [a, b] => u16::from_ne_bytes([*a, *b]) as f32,
+            [a, b, c, d] => f32::from_ne_bytes([*a, *b, *c, *d]),
+            _ => f32::NAN,
+        })
+        .collect()
+}
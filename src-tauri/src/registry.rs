@@ -0,0 +1,185 @@
+// 文件系统访问控制：用户通过对话框或拖放选择过的路径才能登记为实验文件夹；
+// 读取只允许在当前实验登记的文件夹之内，写入只允许在用户选择的导出文件夹之内，且不得覆盖实验的输入文件。
+// 所有路径规范化（解析 .. 与符号链接）后再比较
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+// 用户选择过的路径，保存到磁盘，重新打开历史记录时无需重新选择
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Grants {
+    #[serde(default)]
+    folders: Vec<PathBuf>,
+    #[serde(default)]
+    export_folders: Vec<PathBuf>,
+}
+
+#[derive(Default)]
+pub struct FolderRegistry {
+    grants: RwLock<Grants>,
+    roots: RwLock<Vec<PathBuf>>,
+    store: Option<PathBuf>,
+}
+
+fn poisoned<T>(_: T) -> String {
+    "文件夹登记表已损坏".to_string()
+}
+
+fn within(path: &Path, roots: &[PathBuf]) -> bool {
+    roots.iter().any(|root| path.starts_with(root))
+}
+
+fn canonicalize(path: &Path) -> Result<PathBuf, String> {
+    path.canonicalize()
+        .map_err(|e| format!("无法访问 {}: {}", path.display(), e))
+}
+
+// 待写入的路径可能尚不存在：规范化最近的已存在上级，其余部分不得包含 .. 等成分
+fn canonicalize_for_write(path: &Path) -> Result<PathBuf, String> {
+    let mut existing = path;
+    let mut missing = Vec::new();
+    while existing.symlink_metadata().is_err() {
+        let name = existing
+            .file_name()
+            .ok_or_else(|| format!("无效的输出路径: {}", path.display()))?;
+        missing.push(name);
+        existing = existing
+            .parent()
+            .ok_or_else(|| format!("无效的输出路径: {}", path.display()))?;
+    }
+
+    let mut canonical = canonicalize(existing)?;
+    canonical.extend(missing.into_iter().rev());
+    Ok(canonical)
+}
+
+impl FolderRegistry {
+    // 从 store 读取之前选择过的路径；文件不存在或无法解析时从空白开始
+    pub fn load(store: PathBuf) -> Self {
+        let grants = fs::read_to_string(&store)
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default();
+        FolderRegistry {
+            grants: RwLock::new(grants),
+            roots: RwLock::default(),
+            store: Some(store),
+        }
+    }
+
+    // 记录用户通过对话框或拖放选择的文件夹或文件
+    pub fn grant(&self, paths: &[PathBuf]) -> Result<(), String> {
+        let mut grants = self.grants.write().map_err(poisoned)?;
+        for path in paths {
+            let path = canonicalize(path)?;
+            if !within(&path, &grants.folders) {
+                grants.folders.push(path);
+            }
+        }
+        self.save(&grants)
+    }
+
+    // 记录用户选择的导出文件夹
+    pub fn grant_export(&self, folder: &Path) -> Result<(), String> {
+        let mut grants = self.grants.write().map_err(poisoned)?;
+        let folder = canonicalize(folder)?;
+        if !within(&folder, &grants.export_folders) {
+            grants.export_folders.push(folder);
+        }
+        self.save(&grants)
+    }
+
+    fn save(&self, grants: &Grants) -> Result<(), String> {
+        let Some(store) = &self.store else {
+            return Ok(());
+        };
+        if let Some(parent) = store.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("无法创建文件夹 {}: {}", parent.display(), e))?;
+        }
+        let text = serde_json::to_string_pretty(grants).map_err(|e| format!("序列化失败: {}", e))?;
+        fs::write(store, text).map_err(|e| format!("保存已选择的文件夹失败 {}: {}", store.display(), e))
+    }
+
+    // 替换为新实验的文件夹（GT 为 COCO 标注文件时为该文件本身），返回规范化后的路径
+    pub fn register(&self, folders: &[String]) -> Result<Vec<PathBuf>, String> {
+        let grants = self.grants.read().map_err(poisoned)?;
+        let mut roots = Vec::new();
+        for folder in folders.iter().filter(|f| !f.is_empty()) {
+            let root = canonicalize(Path::new(folder))?;
+            if !within(&root, &grants.folders) {
+                return Err(format!("文件夹未经用户选择，请通过对话框或拖放重新选择: {}", folder));
+            }
+            roots.push(root);
+        }
+        *self.roots.write().map_err(poisoned)? = roots.clone();
+        Ok(roots)
+    }
+
+    // 存在但不在用户选择过的路径之内的文件夹；不存在的留给验证报告
+    pub fn ungranted(&self, folders: &[String]) -> Result<Vec<String>, String> {
+        let grants = self.grants.read().map_err(poisoned)?;
+        Ok(folders
+            .iter()
+            .filter(|folder| !folder.is_empty())
+            .filter(|folder| Path::new(folder.as_str()).canonicalize().is_ok_and(|root| !within(&root, &grants.folders)))
+            .cloned()
+            .collect())
+    }
+
+    // 读取实验文件：规范化后必须位于某个登记的文件夹之内
+    pub fn resolve(&self, path: &str) -> Result<PathBuf, String> {
+        let canonical = canonicalize(Path::new(path))?;
+        let roots = self.roots.read().map_err(poisoned)?;
+        if within(&canonical, &roots) {
+            Ok(canonical)
+        } else {
+            Err(format!("文件不在当前实验登记的文件夹中: {}", path))
+        }
+    }
+
+    // 确认所有路径都位于当前实验登记的文件夹中，空路径表示未使用的角色
+    pub fn check_all<'a>(&self, paths: impl IntoIterator<Item = &'a str>) -> Result<(), String> {
+        for path in paths.into_iter().filter(|p| !p.is_empty()) {
+            self.resolve(path)?;
+        }
+        Ok(())
+    }
+
+    // 将路径替换为规范化后的路径，之后只读取规范化后的路径，避免检查之后被替换为指向别处的符号链接；空路径表示未使用的角色
+    pub fn resolve_all<'a>(&self, paths: impl IntoIterator<Item = &'a mut String>) -> Result<(), String> {
+        for path in paths.into_iter().filter(|p| !p.is_empty()) {
+            *path = self
+                .resolve(path)?
+                .into_os_string()
+                .into_string()
+                .map_err(|p| format!("路径包含无效字符: {}", p.to_string_lossy()))?;
+        }
+        Ok(())
+    }
+
+    // 登记实验之前查看用户选择的路径（如判断拖放的是否为文件），也允许当前实验的文件
+    pub fn resolve_selected(&self, path: &str) -> Result<PathBuf, String> {
+        let canonical = canonicalize(Path::new(path))?;
+        let grants = self.grants.read().map_err(poisoned)?;
+        if within(&canonical, &grants.folders) || within(&canonical, &self.roots.read().map_err(poisoned)?) {
+            Ok(canonical)
+        } else {
+            Err(format!("路径未经用户选择: {}", path))
+        }
+    }
+
+    // 写入文件或创建文件夹：必须位于用户选择的导出文件夹之内，且不能覆盖实验的输入文件
+    pub fn resolve_output(&self, path: &Path) -> Result<PathBuf, String> {
+        let target = canonicalize_for_write(path)?;
+        let grants = self.grants.read().map_err(poisoned)?;
+        if !within(&target, &grants.export_folders) {
+            return Err(format!("只能写入选择的导出文件夹: {}", path.display()));
+        }
+        if target.is_file() && within(&target, &self.roots.read().map_err(poisoned)?) {
+            return Err(format!("不能覆盖实验的输入文件: {}", path.display()));
+        }
+        Ok(target)
+    }
+}
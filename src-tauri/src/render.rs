@@ -0,0 +1,50 @@
+// 图像渲染的公共工具：读取可显示的图片、掩码转图片以及 PNG 数据 URL 编码
+
+use crate::mask::{self, MaskData};
+use crate::volume;
+use base64::Engine;
+use image::{DynamicImage, GrayImage, Rgb, RgbImage};
+use std::io::Cursor;
+
+// 读取为可显示的图片；数组与浮点 TIFF 等按掩码读取后显示为黑白图
+pub fn load_display_image(path: &str) -> Result<DynamicImage, String> {
+    if volume::is_volume_file(path) {
+        return Err("体数据请按切片查看".to_string());
+    }
+    image::open(path).or_else(|_| mask::load_mask(path).map(|mask| mask_to_image(&mask)))
+}
+
+// 掩码按前景显示为白色、背景为黑色
+pub fn mask_to_image(mask: &MaskData) -> DynamicImage {
+    DynamicImage::ImageLuma8(GrayImage::from_fn(mask.width, mask.height, |x, y| {
+        image::Luma([if mask.is_foreground(x, y) { 255 } else { 0 }])
+    }))
+}
+
+// 按热力色带（黑、红、黄、白）渲染 0 到 1 的数值，超出范围的值截断
+pub fn heatmap_image(values: &[f32], width: u32, height: u32) -> DynamicImage {
+    DynamicImage::ImageRgb8(RgbImage::from_fn(width, height, |x, y| {
+        heat_color(values[y as usize * width as usize + x as usize])
+    }))
+}
+
+fn heat_color(value: f32) -> Rgb<u8> {
+    let t = value.clamp(0.0, 1.0) * 3.0;
+    let channel = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+    Rgb([channel(t), channel(t - 1.0), channel(t - 2.0)])
+}
+
+pub fn png_bytes(image: &DynamicImage) -> Result<Vec<u8>, String> {
+    let mut buffer = Cursor::new(Vec::new());
+    image
+        .write_to(&mut buffer, image::ImageOutputFormat::Png)
+        .map_err(|e| format!("PNG 编码失败: {}", e))?;
+    Ok(buffer.into_inner())
+}
+
+pub fn png_data_url(image: &DynamicImage) -> Result<String, String> {
+    Ok(format!(
+        "data:image/png;base64,{}",
+        base64::engine::general_purpose::STANDARD.encode(png_bytes(image)?)
+    ))
+}
@@ -0,0 +1,447 @@
+// 比较结果的 HTML 报告：单个自包含文件，包含运行配置、每个指标各模型的汇总表与分布箱线图，
+// 以及每个模型最好与最差的若干案例，缩略图与误差叠加图以数据 URL 内嵌，无需安装本程序即可查看
+
+use crate::export::CollisionPolicy;
+use crate::overlay::OverlayOptions;
+use crate::palette::Palette;
+use crate::roles::{RoleKeys, RoleSet};
+use crate::{render, spreadsheet, summary, ComparisonResult};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+#[derive(Debug, Deserialize)]
+pub struct ReportRequest {
+    pub results: Vec<ComparisonResult>,
+    pub export_folder: String,
+    // 文件名（不含 .html），默认 report
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub on_collision: CollisionPolicy,
+    #[serde(default)]
+    pub title: Option<String>,
+    // 模型的排列顺序，未列出的模型按名称排在后面
+    #[serde(default)]
+    pub models: Option<Vec<String>>,
+    // 运行配置（文件夹、匹配规则等）
+    #[serde(default)]
+    pub configuration: serde_json::Value,
+    // 每个模型展示的最好与最差案例数
+    #[serde(default = "default_cases")]
+    pub cases: usize,
+    // 挑选案例所依据的指标
+    #[serde(default = "default_rank_metric")]
+    pub rank_metric: String,
+    #[serde(default = "default_thumbnail_size")]
+    pub thumbnail_size: u32,
+    // paths 中 GT 与原始图片对应的键，默认取自 roles；原始图片的键为空或不存在时不显示原图
+    #[serde(default)]
+    pub gt_key: Option<String>,
+    #[serde(default)]
+    pub original_key: Option<String>,
+    // 彩色掩膜的类别调色板，误差叠加图据此读取掩膜
+    #[serde(default)]
+    pub palette: Option<Palette>,
+    #[serde(default)]
+    pub overlay: OverlayOptions,
+    // 标记为我的方法的方法在汇总表与案例中突出显示
+    #[serde(default)]
+    pub roles: Option<RoleSet>,
+}
+
+fn default_cases() -> usize {
+    3
+}
+
+fn default_rank_metric() -> String {
+    "iou".to_string()
+}
+
+fn default_thumbnail_size() -> u32 {
+    256
+}
+
+const COLORS: [&str; 8] = [
+    "#4e79a7", "#f28e2b", "#e15759", "#76b7b2", "#59a14f", "#edc948", "#b07aa1", "#ff9da7",
+];
+
+const STYLE: &str = "
+body { font-family: -apple-system, 'Segoe UI', 'PingFang SC', 'Microsoft YaHei', sans-serif; margin: 2em auto; max-width: 1200px; color: #222; padding: 0 1em; }
+h1 { margin-bottom: 0.2em; }
+.meta { color: #666; margin-top: 0; }
+table { border-collapse: collapse; margin: 0.5em 0 1.5em; }
+th, td { border-bottom: 1px solid #ddd; padding: 4px 10px; text-align: right; }
+th:first-child, td:first-child { text-align: left; }
+th { background: #f5f5f5; }
+.plot { margin-bottom: 2em; }
+.cases { display: flex; flex-wrap: wrap; gap: 12px; margin-bottom: 1em; }
+.case { border: 1px solid #ddd; border-radius: 6px; padding: 8px; }
+.case h4 { margin: 0 0 4px; font-size: 14px; word-break: break-all; }
+.case .scores { color: #555; font-size: 12px; margin-bottom: 6px; }
+.images { display: flex; gap: 6px; }
+figure { margin: 0; text-align: center; font-size: 12px; color: #666; }
+figure img { display: block; max-width: 100%; image-rendering: pixelated; }
+.missing { display: flex; align-items: center; justify-content: center; background: #f5f5f5; color: #999; font-size: 12px; padding: 8px; }
+";
+
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn format_score(value: Option<f64>) -> String {
+    match value {
+        Some(v) if v.is_finite() => format!("{:.4}", v),
+        Some(v) => v.to_string(),
+        None => "NA".to_string(),
+    }
+}
+
+// 已排序数据的分位数，相邻两值之间线性插值
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    let position = q * (sorted.len() - 1) as f64;
+    let (lower, upper) = (position.floor() as usize, position.ceil() as usize);
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (position - lower as f64)
+}
+
+// 每个模型一个箱线图：箱体为四分位数，横线为中位数，须为最小与最大值，圆点为均值
+fn box_plot(results: &[ComparisonResult], models: &[String], metric: &str) -> String {
+    let series: Vec<(usize, Vec<f64>)> = models
+        .iter()
+        .enumerate()
+        .map(|(i, model)| {
+            let mut values = summary::values(results, model, metric);
+            values.sort_by(f64::total_cmp);
+            (i, values)
+        })
+        .filter(|(_, values)| !values.is_empty())
+        .collect();
+    if series.is_empty() {
+        return String::new();
+    }
+
+    // IOU 等比例类指标固定为 0 到 1，距离类指标从 0 到最大值
+    let top = if summary::lower_is_better(metric) {
+        series.iter().map(|(_, v)| v[v.len() - 1]).fold(0.0, f64::max).max(f64::EPSILON)
+    } else {
+        1.0
+    };
+    let (left, plot_top, plot_height, slot) = (56.0, 16.0, 200.0, 110.0);
+    let width = left + slot * series.len() as f64 + 16.0;
+    let height = plot_top + plot_height + 40.0;
+    let y = |value: f64| plot_top + plot_height * (1.0 - (value / top).clamp(0.0, 1.0));
+
+    let mut svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}" font-size="11">"#,
+        width, height
+    );
+    for tick in 0..=4 {
+        let value = top * tick as f64 / 4.0;
+        svg.push_str(&format!(
+            r##"<line x1="{l}" x2="{r}" y1="{y:.1}" y2="{y:.1}" stroke="#eee"/><text x="{t}" y="{ty:.1}" text-anchor="end" fill="#666">{v:.2}</text>"##,
+            l = left,
+            r = width - 16.0,
+            y = y(value),
+            t = left - 6.0,
+            ty = y(value) + 4.0,
+            v = value
+        ));
+    }
+
+    for (slot_index, (model_index, values)) in series.iter().enumerate() {
+        let color = COLORS[model_index % COLORS.len()];
+        let center = left + slot * (slot_index as f64 + 0.5);
+        let (q1, median, q3) = (quantile(values, 0.25), quantile(values, 0.5), quantile(values, 0.75));
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        let half = slot * 0.25;
+        svg.push_str(&format!(
+            r##"<line x1="{c}" x2="{c}" y1="{min:.1}" y2="{max:.1}" stroke="{color}"/>"##,
+            c = center,
+            min = y(values[0]),
+            max = y(values[values.len() - 1]),
+            color = color
+        ));
+        svg.push_str(&format!(
+            r##"<rect x="{x}" y="{y:.1}" width="{w}" height="{h:.1}" fill="{color}" fill-opacity="0.35" stroke="{color}"/>"##,
+            x = center - half,
+            y = y(q3),
+            w = half * 2.0,
+            h = (y(q1) - y(q3)).max(1.0),
+            color = color
+        ));
+        svg.push_str(&format!(
+            r##"<line x1="{a}" x2="{b}" y1="{m:.1}" y2="{m:.1}" stroke="{color}" stroke-width="2"/><circle cx="{c}" cy="{mean:.1}" r="3" fill="#fff" stroke="{color}"/>"##,
+            a = center - half,
+            b = center + half,
+            m = y(median),
+            c = center,
+            mean = y(mean),
+            color = color
+        ));
+        svg.push_str(&format!(
+            r##"<text x="{c}" y="{ty:.1}" text-anchor="middle"><title>{name}</title>{label}</text>"##,
+            c = center,
+            ty = plot_top + plot_height + 16.0,
+            name = escape_html(&models[*model_index]),
+            label = escape_html(&truncate(&models[*model_index], 16))
+        ));
+        svg.push_str(&format!(
+            r##"<text x="{c}" y="{ty:.1}" text-anchor="middle" fill="#999">n={n}</text>"##,
+            c = center,
+            ty = plot_top + plot_height + 30.0,
+            n = values.len()
+        ));
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let mut truncated: String = text.chars().take(max_chars - 1).collect();
+    truncated.push('…');
+    truncated
+}
+
+// 案例中的缩略图，同一文件只编码一次；读取失败时显示原因而不中断报告
+struct Thumbnails<'a> {
+    request: &'a ReportRequest,
+    keys: RoleKeys,
+    cache: HashMap<String, String>,
+}
+
+impl Thumbnails<'_> {
+    fn encode(&self, image: Result<image::DynamicImage, String>) -> String {
+        let size = self.request.thumbnail_size.max(1);
+        match image.and_then(|image| render::png_data_url(&image.thumbnail(size, size))) {
+            Ok(url) => format!(r#"<img src="{}" width="{}">"#, url, size),
+            Err(e) => format!(
+                r#"<div class="missing" style="width:{0}px;height:{0}px">{1}</div>"#,
+                size,
+                escape_html(&e)
+            ),
+        }
+    }
+
+    fn image(&mut self, path: &str) -> String {
+        if let Some(html) = self.cache.get(path) {
+            return html.clone();
+        }
+        let html = self.encode(render::load_display_image(path));
+        self.cache.insert(path.to_string(), html.clone());
+        html
+    }
+
+    fn overlay(&self, result: &ComparisonResult, gt_path: &str, pred_path: &str) -> String {
+        let original = self
+            .keys
+            .original
+            .as_ref()
+            .and_then(|key| result.paths.get(key))
+            .map(String::as_str);
+        self.encode(crate::render_error_overlay(
+            &result.filename,
+            gt_path,
+            pred_path,
+            original,
+            self.request.palette.as_ref(),
+            &self.request.overlay,
+        ))
+    }
+}
+
+fn figure(caption: &str, content: String) -> String {
+    format!("<figure>{}<figcaption>{}</figcaption></figure>", content, escape_html(caption))
+}
+
+fn case_card(thumbnails: &mut Thumbnails, result: &ComparisonResult, model: &str) -> String {
+    let keys = thumbnails.keys.clone();
+    let name = if result.group.is_empty() {
+        result.filename.clone()
+    } else {
+        format!("{}/{}", result.group, result.filename)
+    };
+    let scores: Vec<String> = summary::METRICS
+        .iter()
+        .filter_map(|metric| {
+            let value = summary::scores(result, metric)?.get(model)?;
+            Some(format!("{} {}", summary::metric_title(metric), format_score(Some(*value))))
+        })
+        .collect();
+
+    let mut images = Vec::new();
+    if let Some(path) = keys.original.as_ref().and_then(|key| result.paths.get(key)) {
+        images.push(figure("原始图片", thumbnails.image(path)));
+    }
+    let gt_path = result.paths.get(&keys.gt);
+    if let Some(path) = gt_path {
+        images.push(figure("GT", thumbnails.image(path)));
+    }
+    if let Some(path) = result.paths.get(model) {
+        images.push(figure(model, thumbnails.image(path)));
+        if let Some(gt_path) = gt_path {
+            images.push(figure("误差叠加", thumbnails.overlay(result, gt_path, path)));
+        }
+    }
+
+    format!(
+        r#"<div class="case"><h4>{}</h4><div class="scores">{}</div><div class="images">{}</div></div>"#,
+        escape_html(&name),
+        escape_html(&scores.join(" · ")),
+        images.join("")
+    )
+}
+
+// 按挑选指标排序后的最好与最差案例，两者不重复
+fn ranked_cases<'a>(
+    results: &'a [ComparisonResult],
+    model: &str,
+    metric: &str,
+    count: usize,
+) -> (Vec<&'a ComparisonResult>, Vec<&'a ComparisonResult>) {
+    let mut ranked: Vec<(&ComparisonResult, f64)> = results
+        .iter()
+        .filter_map(|r| {
+            let value = *summary::scores(r, metric)?.get(model)?;
+            value.is_finite().then_some((r, value))
+        })
+        .collect();
+    ranked.sort_by(|a, b| a.1.total_cmp(&b.1));
+    if !summary::lower_is_better(metric) {
+        ranked.reverse();
+    }
+    let best: Vec<_> = ranked.iter().take(count).map(|(r, _)| *r).collect();
+    let worst: Vec<_> = ranked.iter().skip(best.len()).rev().take(count).map(|(r, _)| *r).collect();
+    (best, worst)
+}
+
+// 模型名称，我的方法加粗并注明
+fn model_name(model: &str, ours: &[String]) -> String {
+    if ours.iter().any(|o| o == model) {
+        format!("<strong>{}</strong>（我的方法）", escape_html(model))
+    } else {
+        escape_html(model)
+    }
+}
+
+fn summary_table(results: &[ComparisonResult], models: &[String], metric: &str, ours: &[String]) -> String {
+    let mut html = String::from(
+        "<table><tr><th>模型</th><th>n</th><th>均值</th><th>标准差</th><th>中位数</th><th>最小值</th><th>最大值</th><th>95% CI</th></tr>",
+    );
+    for summary in summary::summarize(results, models, &[metric]) {
+        let values = [summary.mean, summary.std, summary.median, summary.min, summary.max];
+        let cells: Vec<String> = values.iter().map(|v| format!("<td>{:.4}</td>", v)).collect();
+        html.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td>{}<td>± {:.4}</td></tr>",
+            model_name(&summary.model, ours),
+            summary.count,
+            cells.join(""),
+            summary.ci95
+        ));
+    }
+    html.push_str("</table>");
+    html
+}
+
+pub fn generate(request: &ReportRequest) -> Result<String, String> {
+    let results = &request.results;
+    if !summary::METRICS.contains(&request.rank_metric.as_str()) {
+        return Err(format!(
+            "未知的指标: {}，可用: {}",
+            request.rank_metric,
+            summary::METRICS.join(", ")
+        ));
+    }
+    let metrics = summary::present_metrics(results);
+    let models = summary::models(results, request.models.as_deref());
+    if models.is_empty() {
+        return Err("没有可汇总的指标".to_string());
+    }
+
+    let ours = request.roles.as_ref().map(RoleSet::require_ours).transpose()?.unwrap_or_default();
+    let title = request.title.clone().unwrap_or_else(|| "实验结果对比报告".to_string());
+    let mut body = format!(
+        r#"<h1>{}</h1><p class="meta">生成于 {} · {} 张图片 · {} 个模型</p>"#,
+        escape_html(&title),
+        chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+        results.len(),
+        models.len()
+    );
+
+    let configuration = spreadsheet::flatten_configuration(&request.configuration);
+    if !configuration.is_empty() {
+        body.push_str("<h2>运行配置</h2><table><tr><th>项</th><th>值</th></tr>");
+        for (key, value) in &configuration {
+            body.push_str(&format!(
+                "<tr><td>{}</td><td style=\"text-align:left\">{}</td></tr>",
+                escape_html(key),
+                escape_html(value)
+            ));
+        }
+        body.push_str("</table>");
+    }
+
+    body.push_str("<h2>指标汇总</h2>");
+    for metric in &metrics {
+        let direction = if summary::lower_is_better(metric) { "越小越好" } else { "越大越好" };
+        body.push_str(&format!(
+            r#"<h3>{}（{}）</h3>{}<div class="plot">{}</div>"#,
+            summary::metric_title(metric),
+            direction,
+            summary_table(results, &models, metric, &ours),
+            box_plot(results, &models, metric)
+        ));
+    }
+
+    if request.cases > 0 {
+        body.push_str(&format!(
+            "<h2>典型案例（按 {} 排序）</h2>",
+            summary::metric_title(&request.rank_metric)
+        ));
+        let mut thumbnails = Thumbnails {
+            request,
+            keys: RoleKeys::resolve(
+                request.gt_key.as_deref(),
+                request.original_key.as_deref(),
+                request.roles.as_ref(),
+            )?,
+            cache: HashMap::new(),
+        };
+        for model in &models {
+            let (best, worst) = ranked_cases(results, model, &request.rank_metric, request.cases);
+            if best.is_empty() {
+                continue;
+            }
+            body.push_str(&format!("<h3>{}</h3>", model_name(model, &ours)));
+            for (label, cases) in [("最好", best), ("最差", worst)] {
+                if cases.is_empty() {
+                    continue;
+                }
+                body.push_str(&format!("<h4>{}</h4><div class=\"cases\">", label));
+                for result in cases {
+                    body.push_str(&case_card(&mut thumbnails, result, model));
+                }
+                body.push_str("</div>");
+            }
+        }
+    }
+
+    Ok(format!(
+        "<!DOCTYPE html>\n<html lang=\"zh-CN\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n{}\n</body>\n</html>\n",
+        escape_html(&title),
+        STYLE,
+        body
+    ))
+}
@@ -0,0 +1,336 @@
+// 论文用的结果汇总表：模型为行、指标为列，单元格为均值 ± 标准差或 95% 置信区间，
+// 每列最优加粗、次优加下划线，可突出显示我的方法；输出 LaTeX（booktabs）或 GitHub Markdown
+
+use crate::roles::RoleSet;
+use crate::summary::{self, lower_is_better, metric_title, MetricSummary};
+use crate::ComparisonResult;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TableFormat {
+    #[default]
+    Latex,
+    Markdown,
+}
+
+// 均值后的 ± 部分
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Spread {
+    #[default]
+    Std,
+    Ci95,
+    None,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TableOptions {
+    #[serde(default)]
+    pub format: TableFormat,
+    // 列的顺序，默认为结果中有值的全部指标
+    #[serde(default)]
+    pub metrics: Option<Vec<String>>,
+    // 行的顺序，未列出的模型按名称排在后面
+    #[serde(default)]
+    pub models: Option<Vec<String>>,
+    #[serde(default)]
+    pub spread: Spread,
+    #[serde(default = "default_decimals")]
+    pub decimals: usize,
+    // IOU、准确率与 Dice 以百分数显示
+    #[serde(default)]
+    pub percent: bool,
+    // 突出显示的模型，默认为 roles 中标记为我的方法的方法
+    #[serde(default)]
+    pub highlight: Option<Vec<String>>,
+    #[serde(default)]
+    pub roles: Option<RoleSet>,
+    // 提供标题或标签时 LaTeX 输出包在 table 环境中
+    #[serde(default)]
+    pub caption: Option<String>,
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+fn default_decimals() -> usize {
+    3
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Rank {
+    Best,
+    Second,
+    Other,
+}
+
+fn escape_latex(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str(r"\textbackslash{}"),
+            '~' => escaped.push_str(r"\textasciitilde{}"),
+            '^' => escaped.push_str(r"\textasciicircum{}"),
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn escape_markdown(text: &str) -> String {
+    text.replace('|', r"\|")
+}
+
+struct Cell {
+    mean: String,
+    spread: Option<String>,
+    rank: Rank,
+}
+
+impl TableOptions {
+    fn highlighted(&self) -> Vec<String> {
+        match (&self.highlight, &self.roles) {
+            (Some(highlight), _) => highlight.clone(),
+            (None, Some(roles)) => roles.ours(),
+            (None, None) => Vec::new(),
+        }
+    }
+
+    fn format_value(&self, metric: &str, value: f64) -> String {
+        let scale = if self.percent && !lower_is_better(metric) { 100.0 } else { 1.0 };
+        format!("{:.*}", self.decimals, value * scale)
+    }
+
+    fn cell(&self, metric: &str, summary: &MetricSummary) -> Cell {
+        let spread = match self.spread {
+            Spread::Std => Some(summary.std),
+            Spread::Ci95 => Some(summary.ci95),
+            Spread::None => None,
+        };
+        Cell {
+            mean: self.format_value(metric, summary.mean),
+            spread: spread.map(|v| self.format_value(metric, v)),
+            rank: Rank::Other,
+        }
+    }
+}
+
+// 按显示的数值排名，数值相同的并列；少于两个模型时不标记
+fn rank_column(cells: &mut [Option<Cell>], means: &[Option<f64>], metric: &str) {
+    let mut distinct: Vec<(f64, &str)> = Vec::new();
+    for (cell, mean) in cells.iter().zip(means) {
+        if let (Some(cell), Some(mean)) = (cell, mean) {
+            if !distinct.iter().any(|(_, text)| *text == cell.mean) {
+                distinct.push((*mean, cell.mean.as_str()));
+            }
+        }
+    }
+    if cells.iter().flatten().count() < 2 {
+        return;
+    }
+    distinct.sort_by(|a, b| a.0.total_cmp(&b.0));
+    if !lower_is_better(metric) {
+        distinct.reverse();
+    }
+    let ranks: Vec<String> = distinct.iter().take(2).map(|(_, text)| text.to_string()).collect();
+
+    for cell in cells.iter_mut().flatten() {
+        if ranks.first() == Some(&cell.mean) {
+            cell.rank = Rank::Best;
+        } else if ranks.get(1) == Some(&cell.mean) {
+            cell.rank = Rank::Second;
+        }
+    }
+}
+
+pub fn generate(results: &[ComparisonResult], options: &TableOptions) -> Result<String, String> {
+    let metrics: Vec<String> = match &options.metrics {
+        Some(metrics) => metrics.clone(),
+        None => summary::present_metrics(results).iter().map(|m| m.to_string()).collect(),
+    };
+    if let Some(unknown) = metrics.iter().find(|m| !summary::METRICS.contains(&m.as_str())) {
+        return Err(format!("未知的指标: {}，可用: {}", unknown, summary::METRICS.join(", ")));
+    }
+    let models = summary::models(results, options.models.as_deref());
+    if models.is_empty() || metrics.is_empty() {
+        return Err("没有可汇总的指标".to_string());
+    }
+    if let (None, Some(roles)) = (&options.highlight, &options.roles) {
+        roles.require_ours()?;
+    }
+
+    // 每列分别排名，cells[列][行]
+    let mut columns = Vec::new();
+    for metric in &metrics {
+        let summaries: Vec<Option<MetricSummary>> = models
+            .iter()
+            .map(|model| summary::summarize(results, std::slice::from_ref(model), &[metric.as_str()]).pop())
+            .collect();
+        let means: Vec<Option<f64>> = summaries.iter().map(|s| s.as_ref().map(|s| s.mean)).collect();
+        let mut cells: Vec<Option<Cell>> = summaries
+            .iter()
+            .map(|s| s.as_ref().map(|s| options.cell(metric, s)))
+            .collect();
+        rank_column(&mut cells, &means, metric);
+        columns.push(cells);
+    }
+
+    let rows: Vec<(&String, Vec<Option<&Cell>>)> = models
+        .iter()
+        .enumerate()
+        .map(|(i, model)| (model, columns.iter().map(|column| column[i].as_ref()).collect()))
+        .collect();
+    Ok(match options.format {
+        TableFormat::Latex => latex(&metrics, &rows, options),
+        TableFormat::Markdown => markdown(&metrics, &rows, options),
+    })
+}
+
+fn latex(metrics: &[String], rows: &[(&String, Vec<Option<&Cell>>)], options: &TableOptions) -> String {
+    let arrow = |metric: &str| if lower_is_better(metric) { r"$\downarrow$" } else { r"$\uparrow$" };
+    let highlighted = options.highlighted();
+    let mut lines = vec![r"% 需要 \usepackage{booktabs}；突出显示我的方法需要 \usepackage[table]{xcolor}".to_string()];
+    let wrapped = options.caption.is_some() || options.label.is_some();
+    if wrapped {
+        lines.push(r"\begin{table}[t]".to_string());
+        lines.push(r"\centering".to_string());
+        if let Some(caption) = &options.caption {
+            lines.push(format!(r"\caption{{{}}}", escape_latex(caption)));
+        }
+        if let Some(label) = &options.label {
+            lines.push(format!(r"\label{{{}}}", label));
+        }
+    }
+    lines.push(format!(r"\begin{{tabular}}{{l{}}}", "c".repeat(metrics.len())));
+    lines.push(r"\toprule".to_string());
+    let header: Vec<String> = metrics
+        .iter()
+        .map(|m| format!("{} {}", metric_title(m), arrow(m)))
+        .collect();
+    lines.push(format!(r"Method & {} \\", header.join(" & ")));
+    lines.push(r"\midrule".to_string());
+
+    for (model, cells) in rows {
+        let values: Vec<String> = cells
+            .iter()
+            .map(|cell| match cell {
+                None => "--".to_string(),
+                Some(cell) => {
+                    let text = match &cell.spread {
+                        Some(spread) => format!(r"{} $\pm$ {}", cell.mean, spread),
+                        None => cell.mean.clone(),
+                    };
+                    match cell.rank {
+                        Rank::Best => format!(r"\textbf{{{}}}", text),
+                        Rank::Second => format!(r"\underline{{{}}}", text),
+                        Rank::Other => text,
+                    }
+                }
+            })
+            .collect();
+        let prefix = if highlighted.contains(model) { r"\rowcolor{gray!15} " } else { "" };
+        lines.push(format!(r"{}{} & {} \\", prefix, escape_latex(model), values.join(" & ")));
+    }
+
+    lines.push(r"\bottomrule".to_string());
+    lines.push(r"\end{tabular}".to_string());
+    if wrapped {
+        lines.push(r"\end{table}".to_string());
+    }
+    lines.join("\n") + "\n"
+}
+
+fn markdown(metrics: &[String], rows: &[(&String, Vec<Option<&Cell>>)], options: &TableOptions) -> String {
+    let arrow = |metric: &str| if lower_is_better(metric) { "↓" } else { "↑" };
+    let highlighted = options.highlighted();
+    let mut lines = Vec::new();
+    if let Some(caption) = &options.caption {
+        lines.push(format!("**{}**", escape_markdown(caption)));
+        lines.push(String::new());
+    }
+    let header: Vec<String> = metrics
+        .iter()
+        .map(|m| format!("{} {}", metric_title(m), arrow(m)))
+        .collect();
+    lines.push(format!("| Method | {} |", header.join(" | ")));
+    lines.push(format!("| :--- |{}", " :---: |".repeat(metrics.len())));
+
+    for (model, cells) in rows {
+        let values: Vec<String> = cells
+            .iter()
+            .map(|cell| match cell {
+                None => "-".to_string(),
+                Some(cell) => {
+                    let text = match &cell.spread {
+                        Some(spread) => format!("{} ± {}", cell.mean, spread),
+                        None => cell.mean.clone(),
+                    };
+                    match cell.rank {
+                        Rank::Best => format!("**{}**", text),
+                        Rank::Second => format!("<u>{}</u>", text),
+                        Rank::Other => text,
+                    }
+                }
+            })
+            .collect();
+        let name = if highlighted.contains(model) {
+            format!("*{}* (ours)", escape_markdown(model))
+        } else {
+            escape_markdown(model)
+        };
+        lines.push(format!("| {} | {} |", name, values.join(" | ")));
+    }
+    lines.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cell(mean: &str) -> Option<Cell> {
+        Some(Cell {
+            mean: mean.to_string(),
+            spread: None,
+            rank: Rank::Other,
+        })
+    }
+
+    fn ranks(cells: &[Option<Cell>]) -> Vec<Option<bool>> {
+        // Some(true) 为最优，Some(false) 为次优
+        cells
+            .iter()
+            .flatten()
+            .map(|c| match c.rank {
+                Rank::Best => Some(true),
+                Rank::Second => Some(false),
+                Rank::Other => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn ties_share_a_rank() {
+        let mut cells = vec![cell("0.90"), cell("0.90"), cell("0.80"), cell("0.70")];
+        let means = [Some(0.9), Some(0.9004), Some(0.8), Some(0.7)];
+        rank_column(&mut cells, &means, "dice");
+        assert_eq!(ranks(&cells), vec![Some(true), Some(true), Some(false), None]);
+    }
+
+    #[test]
+    fn distances_rank_lower_first() {
+        let mut cells = vec![cell("3.0"), None, cell("1.0")];
+        rank_column(&mut cells, &[Some(3.0), None, Some(1.0)], "hd95");
+        assert_eq!(ranks(&cells), vec![Some(false), Some(true)]);
+    }
+
+    #[test]
+    fn single_method_is_not_marked() {
+        let mut cells = vec![cell("0.5")];
+        rank_column(&mut cells, &[Some(0.5)], "iou");
+        assert_eq!(ranks(&cells), vec![None]);
+    }
+}
@@ -0,0 +1,213 @@
+// 文件夹角色：可选的原始图片、一个或多个 GT 来源、任意数量的命名方法（可将一个或多个标记为“我的方法”）
+// 结果中的路径与指标以角色 ID 为键，不依赖文件夹的位置或显示名称
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoleSource {
+    pub id: String,
+    // 显示名称，为空时使用 ID
+    #[serde(default)]
+    pub name: String,
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Method {
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    pub path: String,
+    // 是否为我的方法
+    #[serde(default)]
+    pub ours: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoleSet {
+    #[serde(default)]
+    pub original: Option<RoleSource>,
+    // 第一个 GT 来源作为计算指标的基准
+    pub ground_truths: Vec<RoleSource>,
+    pub methods: Vec<Method>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoleKind {
+    Original,
+    GroundTruth,
+    Method,
+}
+
+// 展开后的单个角色，按 原始图片、GT、方法 的顺序排列
+pub struct Role<'a> {
+    pub kind: RoleKind,
+    pub id: &'a str,
+    pub name: &'a str,
+    pub path: &'a str,
+}
+
+// 请求中 GT 与原始图片在结果 paths 中的键：显式给出的键优先，否则取自角色配置中第一个 GT 与原始图片的 ID；
+// 原始图片的键为空时不使用原始图片
+#[derive(Debug, Clone)]
+pub struct RoleKeys {
+    pub gt: String,
+    pub original: Option<String>,
+}
+
+impl RoleKeys {
+    pub fn resolve(gt_key: Option<&str>, original_key: Option<&str>, roles: Option<&RoleSet>) -> Result<RoleKeys, String> {
+        let gt = match gt_key {
+            Some(key) => key.to_string(),
+            None => roles
+                .and_then(|roles| roles.ground_truths.first())
+                .map(|source| source.id.clone())
+                .ok_or("请提供 GT 的键（gt_key）或角色配置（roles）")?,
+        };
+        let original = match original_key {
+            Some(key) => Some(key.to_string()),
+            None => roles.and_then(|roles| roles.original.as_ref()).map(|source| source.id.clone()),
+        };
+        Ok(RoleKeys {
+            gt,
+            original: original.filter(|key| !key.is_empty()),
+        })
+    }
+}
+
+// 旧版接口中固定使用的角色 ID，保持与已有的结果和缓存兼容
+const LEGACY_ORIGINAL: &str = "原始图片";
+const LEGACY_GT: &str = "GT";
+const LEGACY_MINE: &str = "我的结果";
+
+impl RoleSet {
+    // 旧版按参数区分的文件夹：原始图片、GT、我的实验数据与若干对照实验
+    pub fn from_legacy(original: String, gt: String, mine: String, comparisons: Vec<(String, String)>) -> RoleSet {
+        let mut methods = vec![Method {
+            id: LEGACY_MINE.to_string(),
+            name: "我的实验数据".to_string(),
+            path: mine,
+            ours: true,
+        }];
+        methods.extend(comparisons.into_iter().map(|(name, path)| Method {
+            id: name.clone(),
+            name,
+            path,
+            ours: false,
+        }));
+
+        RoleSet {
+            original: Some(RoleSource {
+                id: LEGACY_ORIGINAL.to_string(),
+                name: LEGACY_ORIGINAL.to_string(),
+                path: original,
+            }),
+            ground_truths: vec![RoleSource {
+                id: LEGACY_GT.to_string(),
+                name: LEGACY_GT.to_string(),
+                path: gt,
+            }],
+            methods,
+        }
+    }
+
+    // 旧版按位置区分的文件夹列表：[原始图片, GT, 我的实验数据, 对照实验...]
+    pub fn from_folders(folders: &[String]) -> Result<RoleSet, String> {
+        let [original, gt, mine, comparisons @ ..] = folders else {
+            return Err("至少需要选择3个文件夹".to_string());
+        };
+        let comparisons = comparisons
+            .iter()
+            .enumerate()
+            .map(|(index, path)| (format!("对照实验 {}", index + 1), path.clone()))
+            .collect();
+        Ok(RoleSet::from_legacy(original.clone(), gt.clone(), mine.clone(), comparisons))
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.ground_truths.is_empty() {
+            return Err("至少需要一个 GT 来源".to_string());
+        }
+        if self.methods.is_empty() {
+            return Err("至少需要一个方法".to_string());
+        }
+        self.validate_ids()
+    }
+
+    // 无参考模式不需要 GT，但至少需要两个方法才能比较
+    pub fn validate_reference_free(&self) -> Result<(), String> {
+        if self.methods.len() < 2 {
+            return Err("无参考模式至少需要两个方法".to_string());
+        }
+        self.validate_ids()
+    }
+
+    fn validate_ids(&self) -> Result<(), String> {
+        let mut ids = HashSet::new();
+        for role in self.roles() {
+            if role.id.is_empty() {
+                return Err(format!("'{}' 的 ID 不能为空", role.name));
+            }
+            if !ids.insert(role.id) {
+                return Err(format!("角色 ID 重复: {}", role.id));
+            }
+            if role.path.is_empty() {
+                return Err(format!("'{}' 没有选择文件夹", role.name));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn roles(&self) -> Vec<Role<'_>> {
+        let mut roles = Vec::new();
+        roles.extend(self.original.iter().map(|s| s.role(RoleKind::Original)));
+        roles.extend(self.ground_truths.iter().map(|s| s.role(RoleKind::GroundTruth)));
+        roles.extend(self.methods.iter().map(|m| Role {
+            kind: RoleKind::Method,
+            id: &m.id,
+            name: display_name(&m.id, &m.name),
+            path: &m.path,
+        }));
+        roles
+    }
+
+    // 标记为我的方法的方法 ID，汇总表、报告与对比图默认突出显示这些方法
+    pub fn ours(&self) -> Vec<String> {
+        self.methods.iter().filter(|m| m.ours).map(|m| m.id.clone()).collect()
+    }
+
+    // 需要突出显示我的方法时（汇总表、报告、对比图）至少要标记一个；验证、预检与热力图等不要求
+    pub fn require_ours(&self) -> Result<Vec<String>, String> {
+        let ours = self.ours();
+        if ours.is_empty() {
+            return Err("至少需要将一个方法标记为我的方法".to_string());
+        }
+        Ok(ours)
+    }
+
+    // 调用前需通过 validate，保证至少有一个 GT
+    pub fn primary_gt(&self) -> &RoleSource {
+        &self.ground_truths[0]
+    }
+}
+
+impl RoleSource {
+    fn role(&self, kind: RoleKind) -> Role<'_> {
+        Role {
+            kind,
+            id: &self.id,
+            name: display_name(&self.id, &self.name),
+            path: &self.path,
+        }
+    }
+}
+
+fn display_name<'a>(id: &'a str, name: &'a str) -> &'a str {
+    if name.is_empty() {
+        id
+    } else {
+        name
+    }
+}
@@ -0,0 +1,167 @@
+// 文件夹扫描选项：可选递归扫描子文件夹，文件以相对路径作为标识；支持包含/排除的 glob 过滤与数据划分列表
+
+use crate::registry::FolderRegistry;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScanOptions {
+    // 递归扫描子文件夹（如 Cityscapes 的 city/frame.png），文件键为以 / 分隔的相对路径
+    #[serde(default)]
+    pub recursive: bool,
+    // 默认跳过隐藏文件（以 . 开头，包括 macOS 的 ._* 资源文件）
+    #[serde(default)]
+    pub include_hidden: bool,
+    // glob 模式按相对路径匹配；include 为空时不限制
+    #[serde(default)]
+    pub include: Vec<String>,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    // 数据划分文件（每行一个文件名或主文件名），只保留其中列出的共有文件；须为用户选择过的文件，与角色文件夹一同登记
+    #[serde(default)]
+    pub split_file: Option<String>,
+}
+
+// 各个过滤条件去除的文件数量
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FilterStats {
+    pub hidden: usize,
+    pub not_included: usize,
+    pub excluded: usize,
+}
+
+impl ScanOptions {
+    // 依次应用隐藏文件、包含与排除过滤
+    pub fn apply(&self, files: Vec<String>) -> Result<(Vec<String>, FilterStats), String> {
+        let include = build_glob_set(&self.include)?;
+        let exclude = build_glob_set(&self.exclude)?;
+        let mut stats = FilterStats::default();
+
+        let files = files
+            .into_iter()
+            .filter(|file| {
+                if !self.include_hidden && file.split('/').any(|part| part.starts_with('.')) {
+                    stats.hidden += 1;
+                    return false;
+                }
+                if !self.include.is_empty() && !include.is_match(file) {
+                    stats.not_included += 1;
+                    return false;
+                }
+                if exclude.is_match(file) {
+                    stats.excluded += 1;
+                    return false;
+                }
+                true
+            })
+            .collect();
+
+        Ok((files, stats))
+    }
+
+    // 读取数据划分文件，忽略空行与 # 开头的注释；文件须位于当前实验登记的路径之内
+    pub fn load_split(&self, registry: &FolderRegistry) -> Result<Option<HashSet<String>>, String> {
+        let Some(path) = self.split_file.as_deref().filter(|path| !path.is_empty()) else {
+            return Ok(None);
+        };
+        let path = registry.resolve(path)?;
+        let content = fs::read_to_string(&path).map_err(|e| format!("无法读取数据划分文件: {}", e))?;
+
+        Ok(Some(
+            content
+                .lines()
+                .map(|line| line.trim().replace('\\', "/"))
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .collect(),
+        ))
+    }
+}
+
+fn build_glob_set(patterns: &[String]) -> Result<GlobSet, String> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern).map_err(|e| format!("glob 模式无效 {}: {}", pattern, e))?;
+        builder.add(glob);
+    }
+    builder.build().map_err(|e| format!("glob 模式无效: {}", e))
+}
+
+// 列出文件夹下的所有文件，返回以 / 分隔的相对路径；符号链接的文件夹不会展开，避免循环；
+// 指向文件夹之外的符号链接文件跳过，不能借此读取登记的文件夹之外的文件
+pub fn walk(root: &Path, options: &ScanOptions) -> Vec<String> {
+    let mut files = Vec::new();
+    if let Ok(canonical_root) = root.canonicalize() {
+        walk_into(&canonical_root, &canonical_root, "", options.recursive, &mut files);
+    }
+    files
+}
+
+fn walk_into(root: &Path, dir: &Path, prefix: &str, recursive: bool, files: &mut Vec<String>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let Some(name) = entry.file_name().to_str().map(|n| n.to_string()) else {
+            continue;
+        };
+        let relative = if prefix.is_empty() {
+            name
+        } else {
+            format!("{}/{}", prefix, name)
+        };
+
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            if recursive {
+                walk_into(root, &entry.path(), &relative, recursive, files);
+            }
+        } else if file_type.is_symlink() {
+            let inside = entry.path().canonicalize().is_ok_and(|target| target.starts_with(root) && target.is_file());
+            if inside {
+                files.push(relative);
+            }
+        } else if file_type.is_file() {
+            files.push(relative);
+        }
+    }
+}
+
+// 相对路径所在的子文件夹，顶层文件返回空字符串
+pub fn parent_folder(relative: &str) -> &str {
+    relative.rsplit_once('/').map(|(dir, _)| dir).unwrap_or("")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn walk_skips_symlinks_leaving_the_root() {
+        let base = std::env::temp_dir().join(format!("scan_walk_{}", std::process::id()));
+        let (root, outside) = (base.join("root"), base.join("outside"));
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::create_dir_all(&outside).unwrap();
+        fs::write(root.join("a.png"), b"a").unwrap();
+        fs::write(root.join("sub/b.png"), b"b").unwrap();
+        fs::write(outside.join("secret.png"), b"s").unwrap();
+        std::os::unix::fs::symlink(root.join("a.png"), root.join("link.png")).unwrap();
+        std::os::unix::fs::symlink(outside.join("secret.png"), root.join("escape.png")).unwrap();
+
+        let options = ScanOptions {
+            recursive: true,
+            ..ScanOptions::default()
+        };
+        let mut files = walk(&root, &options);
+        files.sort();
+        assert_eq!(files, ["a.png", "link.png", "sub/b.png"]);
+
+        fs::remove_dir_all(&base).unwrap();
+    }
+}
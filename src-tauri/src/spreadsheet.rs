@@ -0,0 +1,169 @@
+// 逐图指标表：长格式 CSV（每行一个文件、模型与指标）、宽格式 CSV（每个模型的每个指标一列），
+// 以及包含逐图指标、模型汇总与运行配置三个工作表的 xlsx 工作簿
+
+use crate::summary;
+use crate::ComparisonResult;
+use rust_xlsxwriter::{Format, Workbook, Worksheet, XlsxError};
+use serde_json::Value;
+
+fn csv_error(e: impl std::fmt::Display) -> String {
+    format!("生成 CSV 失败: {}", e)
+}
+
+fn xlsx_error(e: XlsxError) -> String {
+    format!("生成 xlsx 失败: {}", e)
+}
+
+pub fn long_csv(results: &[ComparisonResult], models: &[String], metrics: &[&str]) -> Result<Vec<u8>, String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer
+        .write_record(["file", "group", "model", "metric", "value"])
+        .map_err(csv_error)?;
+    for result in results {
+        for model in models {
+            for metric in metrics {
+                let Some(value) = summary::scores(result, metric).and_then(|s| s.get(model)) else {
+                    continue;
+                };
+                writer
+                    .write_record([&result.filename, &result.group, model, *metric, &value.to_string()])
+                    .map_err(csv_error)?;
+            }
+        }
+    }
+    writer.into_inner().map_err(csv_error)
+}
+
+fn wide_header(models: &[String], metrics: &[&str]) -> Vec<String> {
+    let mut header = vec!["file".to_string(), "group".to_string()];
+    for model in models {
+        header.extend(metrics.iter().map(|metric| format!("{}_{}", model, metric)));
+    }
+    header
+}
+
+// 宽格式中一张图片的各列，缺失的值为 None
+fn wide_values(result: &ComparisonResult, models: &[String], metrics: &[&str]) -> Vec<Option<f64>> {
+    models
+        .iter()
+        .flat_map(|model| {
+            metrics
+                .iter()
+                .map(move |metric| summary::scores(result, metric).and_then(|s| s.get(model)).copied())
+        })
+        .collect()
+}
+
+pub fn wide_csv(results: &[ComparisonResult], models: &[String], metrics: &[&str]) -> Result<Vec<u8>, String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(wide_header(models, metrics)).map_err(csv_error)?;
+    for result in results {
+        let mut record = vec![result.filename.clone(), result.group.clone()];
+        record.extend(
+            wide_values(result, models, metrics)
+                .into_iter()
+                .map(|value| value.map(|v| v.to_string()).unwrap_or_default()),
+        );
+        writer.write_record(&record).map_err(csv_error)?;
+    }
+    writer.into_inner().map_err(csv_error)
+}
+
+// 嵌套的配置展开为 (键, 值)，键以 . 连接，数组元素为对象时以 [序号] 区分
+pub fn flatten_configuration(value: &Value) -> Vec<(String, String)> {
+    fn scalar(value: &Value) -> String {
+        match value {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        }
+    }
+
+    fn walk(prefix: String, value: &Value, rows: &mut Vec<(String, String)>) {
+        match value {
+            Value::Null => {}
+            Value::Object(map) => {
+                for (key, value) in map {
+                    let key = if prefix.is_empty() {
+                        key.clone()
+                    } else {
+                        format!("{}.{}", prefix, key)
+                    };
+                    walk(key, value, rows);
+                }
+            }
+            Value::Array(items) if items.iter().any(|v| v.is_object() || v.is_array()) => {
+                for (i, item) in items.iter().enumerate() {
+                    walk(format!("{}[{}]", prefix, i), item, rows);
+                }
+            }
+            Value::Array(items) => rows.push((prefix, items.iter().map(scalar).collect::<Vec<_>>().join(", "))),
+            other => rows.push((prefix, scalar(other))),
+        }
+    }
+
+    let mut rows = Vec::new();
+    walk(String::new(), value, &mut rows);
+    rows
+}
+
+fn write_header(sheet: &mut Worksheet, header: &[String], bold: &Format) -> Result<(), XlsxError> {
+    for (col, title) in header.iter().enumerate() {
+        sheet.write_string_with_format(0, col as u16, title, bold)?;
+    }
+    sheet.set_freeze_panes(1, 0)?;
+    Ok(())
+}
+
+pub fn workbook(
+    results: &[ComparisonResult],
+    models: &[String],
+    metrics: &[&str],
+    configuration: &[(String, String)],
+) -> Result<Vec<u8>, String> {
+    let mut workbook = Workbook::new();
+    let bold = Format::new().set_bold();
+
+    let sheet = workbook.add_worksheet().set_name("逐图指标").map_err(xlsx_error)?;
+    write_header(sheet, &wide_header(models, metrics), &bold).map_err(xlsx_error)?;
+    for (row, result) in results.iter().enumerate() {
+        let row = row as u32 + 1;
+        sheet.write_string(row, 0, &result.filename).map_err(xlsx_error)?;
+        sheet.write_string(row, 1, &result.group).map_err(xlsx_error)?;
+        for (col, value) in wide_values(result, models, metrics).into_iter().enumerate() {
+            if let Some(value) = value.filter(|v| v.is_finite()) {
+                sheet.write_number(row, col as u16 + 2, value).map_err(xlsx_error)?;
+            }
+        }
+    }
+
+    let sheet = workbook.add_worksheet().set_name("模型汇总").map_err(xlsx_error)?;
+    let header = ["model", "metric", "count", "mean", "std", "median", "min", "max", "ci95"].map(String::from);
+    write_header(sheet, &header, &bold).map_err(xlsx_error)?;
+    for (row, summary) in summary::summarize(results, models, metrics).iter().enumerate() {
+        let row = row as u32 + 1;
+        sheet.write_string(row, 0, &summary.model).map_err(xlsx_error)?;
+        sheet.write_string(row, 1, &summary.metric).map_err(xlsx_error)?;
+        let numbers = [
+            summary.count as f64,
+            summary.mean,
+            summary.std,
+            summary.median,
+            summary.min,
+            summary.max,
+            summary.ci95,
+        ];
+        for (col, value) in numbers.into_iter().enumerate() {
+            sheet.write_number(row, col as u16 + 2, value).map_err(xlsx_error)?;
+        }
+    }
+
+    let sheet = workbook.add_worksheet().set_name("运行配置").map_err(xlsx_error)?;
+    write_header(sheet, &["key".to_string(), "value".to_string()], &bold).map_err(xlsx_error)?;
+    for (row, (key, value)) in configuration.iter().enumerate() {
+        let row = row as u32 + 1;
+        sheet.write_string(row, 0, key).map_err(xlsx_error)?;
+        sheet.write_string(row, 1, value).map_err(xlsx_error)?;
+    }
+
+    workbook.save_to_buffer().map_err(xlsx_error)
+}
@@ -0,0 +1,124 @@
+// 指标汇总：从对比结果中取出每张图片每个模型的指标，计算每个模型的均值、标准差、中位数与 95% 置信区间
+
+use crate::ComparisonResult;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+
+// 结果中的逐图指标，hd95 与 assd 只对体数据计算
+pub const METRICS: [&str; 5] = ["iou", "accuracy", "dice", "hd95", "assd"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricSummary {
+    pub model: String,
+    pub metric: String,
+    // 参与统计的图片数，不含无穷大等非有限值
+    pub count: usize,
+    pub mean: f64,
+    // 样本标准差（n - 1），只有一张图片时为 0
+    pub std: f64,
+    pub median: f64,
+    pub min: f64,
+    pub max: f64,
+    // 均值 95% 置信区间的半宽，按正态近似 1.96 × 标准差 / √n
+    pub ci95: f64,
+}
+
+// 距离类指标越小越好
+pub fn lower_is_better(metric: &str) -> bool {
+    matches!(metric, "hd95" | "assd")
+}
+
+// 表格与图表中的指标名称
+pub fn metric_title(metric: &str) -> &str {
+    match metric {
+        "iou" => "IoU",
+        "accuracy" => "Accuracy",
+        "dice" => "Dice",
+        "hd95" => "HD95",
+        "assd" => "ASSD",
+        other => other,
+    }
+}
+
+pub fn scores<'a>(result: &'a ComparisonResult, metric: &str) -> Option<&'a HashMap<String, f64>> {
+    match metric {
+        "iou" => Some(&result.iou_scores),
+        "accuracy" => Some(&result.accuracy_scores),
+        "dice" => Some(&result.dice_scores),
+        "hd95" => Some(&result.hd95_scores),
+        "assd" => Some(&result.assd_scores),
+        _ => None,
+    }
+}
+
+// 至少一张图片有值的指标，按 METRICS 的顺序
+pub fn present_metrics(results: &[ComparisonResult]) -> Vec<&'static str> {
+    METRICS
+        .into_iter()
+        .filter(|metric| results.iter().any(|r| scores(r, metric).is_some_and(|s| !s.is_empty())))
+        .collect()
+}
+
+// 有指标的模型；提供 order 时按其顺序，未列出的模型按名称排在后面
+pub fn models(results: &[ComparisonResult], order: Option<&[String]>) -> Vec<String> {
+    let mut remaining: BTreeSet<&String> = results
+        .iter()
+        .flat_map(|r| METRICS.iter().filter_map(|m| scores(r, m)).flat_map(|s| s.keys()))
+        .collect();
+    let mut models = Vec::new();
+    for model in order.unwrap_or_default() {
+        if remaining.remove(model) {
+            models.push(model.clone());
+        }
+    }
+    models.extend(remaining.into_iter().cloned());
+    models
+}
+
+pub fn values(results: &[ComparisonResult], model: &str, metric: &str) -> Vec<f64> {
+    results
+        .iter()
+        .filter_map(|r| scores(r, metric)?.get(model).copied())
+        .filter(|v| v.is_finite())
+        .collect()
+}
+
+fn summarize_values(model: &str, metric: &str, mut values: Vec<f64>) -> Option<MetricSummary> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(f64::total_cmp);
+    let count = values.len();
+    let mean = values.iter().sum::<f64>() / count as f64;
+    let std = if count > 1 {
+        (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (count - 1) as f64).sqrt()
+    } else {
+        0.0
+    };
+    let median = if count % 2 == 1 {
+        values[count / 2]
+    } else {
+        (values[count / 2 - 1] + values[count / 2]) / 2.0
+    };
+
+    Some(MetricSummary {
+        model: model.to_string(),
+        metric: metric.to_string(),
+        count,
+        mean,
+        std,
+        median,
+        min: values[0],
+        max: values[count - 1],
+        ci95: 1.96 * std / (count as f64).sqrt(),
+    })
+}
+
+// 每个模型每个指标一行，按 models 与 metrics 的顺序；没有值的组合跳过
+pub fn summarize(results: &[ComparisonResult], models: &[String], metrics: &[&str]) -> Vec<MetricSummary> {
+    models
+        .iter()
+        .flat_map(|model| metrics.iter().map(move |metric| (model, metric)))
+        .filter_map(|(model, metric)| summarize_values(model, metric, values(results, model, metric)))
+        .collect()
+}
@@ -0,0 +1,62 @@
+// 在图片上绘制文字（列标题、图注、动画帧标签）：使用系统中支持中文的字体，找不到时退回常见的西文字体
+
+use image::{Rgb, RgbImage};
+use imageproc::drawing::{draw_text_mut, text_size};
+use rusttype::{Font, Scale};
+use std::sync::OnceLock;
+
+// 按优先级排列：Windows、macOS、Linux 的中文字体，其后为西文字体
+const FONT_CANDIDATES: [&str; 14] = [
+    "C:\\Windows\\Fonts\\msyh.ttc",
+    "C:\\Windows\\Fonts\\msyh.ttf",
+    "C:\\Windows\\Fonts\\simhei.ttf",
+    "/System/Library/Fonts/PingFang.ttc",
+    "/System/Library/Fonts/STHeiti Medium.ttc",
+    "/Library/Fonts/Arial Unicode.ttf",
+    "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/google-noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/truetype/wqy/wqy-microhei.ttc",
+    "C:\\Windows\\Fonts\\arial.ttf",
+    "/System/Library/Fonts/Helvetica.ttc",
+    "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+    "/usr/share/fonts/TTF/DejaVuSans.ttf",
+];
+
+static FONT: OnceLock<Option<Font<'static>>> = OnceLock::new();
+
+pub fn font() -> Result<&'static Font<'static>, String> {
+    FONT.get_or_init(|| {
+        FONT_CANDIDATES.iter().find_map(|path| {
+            let data = std::fs::read(path).ok()?;
+            Font::try_from_vec(data)
+        })
+    })
+    .as_ref()
+    .ok_or_else(|| "未找到可用于绘制文字的系统字体".to_string())
+}
+
+pub fn width(font: &Font, size: f32, text: &str) -> u32 {
+    text_size(Scale::uniform(size), font, text).0.max(0) as u32
+}
+
+// 以 center_x 为中心、top 为上沿绘制一行文字
+pub fn draw_centered(image: &mut RgbImage, font: &Font, color: [u8; 3], center_x: i32, top: i32, size: f32, text: &str) {
+    let x = center_x - width(font, size, text) as i32 / 2;
+    draw_text_mut(image, Rgb(color), x, top, Scale::uniform(size), font, text);
+}
+
+
+// 在左上角绘制白字标签，底下的图片压暗，便于在任意图片上辨认
+pub fn draw_label(image: &mut RgbImage, font: &Font, size: f32, text: &str) {
+    let padding = (size / 4.0).ceil() as u32;
+    let label_width = (width(font, size, text) + padding * 2).min(image.width());
+    let label_height = (size.ceil() as u32 + padding * 2).min(image.height());
+    for y in 0..label_height {
+        for x in 0..label_width {
+            let pixel = image.get_pixel_mut(x, y);
+            pixel.0 = pixel.0.map(|c| (c as f32 * 0.35) as u8);
+        }
+    }
+    draw_text_mut(image, Rgb([255, 255, 255]), padding as i32, padding as i32, Scale::uniform(size), font, text);
+}
@@ -0,0 +1,329 @@
+// 缩略图服务：通过自定义协议提供缩略图、缩放用的金字塔图块与误差叠加图，生成结果缓存在磁盘上
+// 只读取当前实验登记的文件夹之内的文件。请求格式（Windows 上为 http://thumb.localhost/...）：
+//   thumb://localhost/thumbnail?path=<路径>&size=256
+//   thumb://localhost/info?path=<路径>&tile=512           返回图片尺寸与金字塔层数（JSON）
+//   thumb://localhost/tile?path=<路径>&level=1&x=0&y=0&tile=512   level 每增加 1 尺寸缩小一半
+//   thumb://localhost/overlay?gt=<路径>&pred=<路径>&original=<路径>&filename=<文件名>&size=512
+
+use crate::overlay::OverlayOptions;
+use crate::registry::FolderRegistry;
+use crate::render;
+use image::imageops::FilterType;
+use image::DynamicImage;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use tauri::http::{header, Request, Response, StatusCode};
+
+pub const SCHEME: &str = "thumb";
+
+const DEFAULT_THUMBNAIL_SIZE: u32 = 256;
+const DEFAULT_TILE_SIZE: u32 = 512;
+const MAX_SIZE: u32 = 4096;
+
+// 本程序页面的来源（macOS/Linux 与 Windows），只有这些页面可以用 fetch 读取响应
+const APP_ORIGINS: [&str; 3] = ["tauri://localhost", "http://tauri.localhost", "https://tauri.localhost"];
+
+// 缓存临时文件的序号，同时生成同一缓存文件的请求各写各的临时文件
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+// 磁盘缓存的总大小上限，超出时按最近使用时间删除最旧的文件；每写入 SWEEP_INTERVAL 个文件检查一次（包括启动后第一次写入）
+const MAX_CACHE_BYTES: u64 = 512 * 1024 * 1024;
+const SWEEP_INTERVAL: u64 = 64;
+
+// 最近解码的金字塔层（源文件、修改时间、层级），同一层的图块共用，不必每个图块重新解码与缩放整张图片
+type LevelKey = (PathBuf, Option<SystemTime>, u32);
+const DECODED_LEVELS: usize = 2;
+static LEVELS: Mutex<Vec<(LevelKey, Arc<DynamicImage>)>> = Mutex::new(Vec::new());
+
+type ServeError = (StatusCode, String);
+
+pub fn respond(registry: &FolderRegistry, cache_dir: Option<&Path>, request: &Request<Vec<u8>>) -> Response<Vec<u8>> {
+    let query: HashMap<String, String> = url::form_urlencoded::parse(request.uri().query().unwrap_or_default().as_bytes())
+        .into_owned()
+        .collect();
+
+    let result = match request.uri().path().trim_matches('/') {
+        "thumbnail" => thumbnail(registry, cache_dir, &query).map(|png| (png, "image/png")),
+        "tile" => tile(registry, cache_dir, &query).map(|png| (png, "image/png")),
+        "overlay" => error_overlay(registry, cache_dir, &query).map(|png| (png, "image/png")),
+        "info" => info(registry, &query).map(|json| (json, "application/json")),
+        other => Err((StatusCode::NOT_FOUND, format!("未知的请求: {}", other))),
+    };
+
+    let (status, body, content_type) = match result {
+        Ok((body, content_type)) => (StatusCode::OK, body, content_type),
+        Err((status, message)) => (status, message.into_bytes(), "text/plain; charset=utf-8"),
+    };
+    let mut response = Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, content_type);
+    let origin = request.headers().get(header::ORIGIN).and_then(|value| value.to_str().ok());
+    if let Some(origin) = origin.filter(|origin| APP_ORIGINS.contains(origin)) {
+        response = response.header(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin).header(header::VARY, "Origin");
+    }
+    response.body(body).unwrap_or_default()
+}
+
+fn thumbnail(
+    registry: &FolderRegistry,
+    cache_dir: Option<&Path>,
+    query: &HashMap<String, String>,
+) -> Result<Vec<u8>, ServeError> {
+    let path = allowed_path(registry, query, "path")?;
+    let size = number(query, "size", DEFAULT_THUMBNAIL_SIZE)?.clamp(1, MAX_SIZE);
+
+    cached(cache_dir, &["thumbnail", &size.to_string()], &[&path], || {
+        Ok(load_image(&path)?.thumbnail(size, size))
+    })
+}
+
+fn tile(registry: &FolderRegistry, cache_dir: Option<&Path>, query: &HashMap<String, String>) -> Result<Vec<u8>, ServeError> {
+    let path = allowed_path(registry, query, "path")?;
+    let tile_size = number(query, "tile", DEFAULT_TILE_SIZE)?.clamp(16, MAX_SIZE);
+    let level = number(query, "level", 0)?.min(31);
+    let (x, y) = (number(query, "x", 0)?, number(query, "y", 0)?);
+
+    let key = [level, x, y, tile_size].map(|v| v.to_string());
+    cached(cache_dir, &["tile", &key[0], &key[1], &key[2], &key[3]], &[&path], || {
+        let image = decoded_level(&path, level)?;
+        let (width, height) = (image.width(), image.height());
+        let (left, top) = (x as u64 * tile_size as u64, y as u64 * tile_size as u64);
+        if left >= width as u64 || top >= height as u64 {
+            return Err((StatusCode::NOT_FOUND, format!("图块超出范围: level {} ({}, {})", level, x, y)));
+        }
+
+        let (left, top) = (left as u32, top as u32);
+        Ok(image.crop_imm(left, top, tile_size.min(width - left), tile_size.min(height - top)))
+    })
+}
+
+// 解码并缩放到某一层；未命中时持有锁完成解码，同时请求同一层的图块等待后直接复用
+fn decoded_level(path: &Path, level: u32) -> Result<Arc<DynamicImage>, ServeError> {
+    let key: LevelKey = (path.to_path_buf(), fs::metadata(path).and_then(|m| m.modified()).ok(), level);
+    let mut levels = LEVELS
+        .lock()
+        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "图块缓存已损坏".to_string()))?;
+    if let Some(position) = levels.iter().position(|(cached, _)| *cached == key) {
+        let entry = levels.remove(position);
+        let image = entry.1.clone();
+        levels.insert(0, entry);
+        return Ok(image);
+    }
+
+    let image = load_image(path)?;
+    let image = if level == 0 {
+        image
+    } else {
+        let (width, height) = ((image.width() >> level).max(1), (image.height() >> level).max(1));
+        image.resize_exact(width, height, FilterType::Triangle)
+    };
+    let image = Arc::new(image);
+    levels.insert(0, (key, image.clone()));
+    levels.truncate(DECODED_LEVELS);
+    Ok(image)
+}
+
+fn error_overlay(
+    registry: &FolderRegistry,
+    cache_dir: Option<&Path>,
+    query: &HashMap<String, String>,
+) -> Result<Vec<u8>, ServeError> {
+    let gt = allowed_path(registry, query, "gt")?;
+    let pred = allowed_path(registry, query, "pred")?;
+    let original = match query.get("original") {
+        Some(_) => Some(allowed_path(registry, query, "original")?),
+        None => None,
+    };
+    let filename = query.get("filename").cloned().unwrap_or_default();
+    let size = number(query, "size", 0)?.min(MAX_SIZE);
+
+    let mut files = vec![gt.as_path(), pred.as_path()];
+    files.extend(original.as_deref());
+    cached(cache_dir, &["overlay", &filename, &size.to_string()], &files, || {
+        let image = crate::render_error_overlay(
+            &filename,
+            &gt.to_string_lossy(),
+            &pred.to_string_lossy(),
+            original.as_deref().and_then(Path::to_str),
+            None,
+            &OverlayOptions::default(),
+        )
+        .map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, e))?;
+        Ok(if size > 0 { image.thumbnail(size, size) } else { image })
+    })
+}
+
+// 图片尺寸与金字塔层数，层数为缩小到单个图块所需的次数加一
+fn info(registry: &FolderRegistry, query: &HashMap<String, String>) -> Result<Vec<u8>, ServeError> {
+    let path = allowed_path(registry, query, "path")?;
+    let tile_size = number(query, "tile", DEFAULT_TILE_SIZE)?.clamp(16, MAX_SIZE);
+    let (width, height) = match image::image_dimensions(&path) {
+        Ok(dimensions) => dimensions,
+        Err(_) => {
+            let image = load_image(&path)?;
+            (image.width(), image.height())
+        }
+    };
+
+    let mut levels = 1;
+    while (width.max(height) >> (levels - 1)) > tile_size {
+        levels += 1;
+    }
+    let info = serde_json::json!({
+        "width": width,
+        "height": height,
+        "tile": tile_size,
+        "levels": levels,
+    });
+    Ok(info.to_string().into_bytes())
+}
+
+fn allowed_path(registry: &FolderRegistry, query: &HashMap<String, String>, name: &str) -> Result<PathBuf, ServeError> {
+    let path = query
+        .get(name)
+        .ok_or_else(|| (StatusCode::BAD_REQUEST, format!("缺少参数: {}", name)))?;
+    registry.resolve(path).map_err(|e| (StatusCode::FORBIDDEN, e))
+}
+
+fn number(query: &HashMap<String, String>, name: &str, default: u32) -> Result<u32, ServeError> {
+    match query.get(name) {
+        Some(value) => value
+            .parse()
+            .map_err(|_| (StatusCode::BAD_REQUEST, format!("参数 {} 无效: {}", name, value))),
+        None => Ok(default),
+    }
+}
+
+fn load_image(path: &Path) -> Result<DynamicImage, ServeError> {
+    render::load_display_image(&path.to_string_lossy()).map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, e))
+}
+
+// 按请求参数与源文件的大小、修改时间生成缓存键，源文件变化后自动失效
+fn cached(
+    cache_dir: Option<&Path>,
+    params: &[&str],
+    files: &[&Path],
+    render: impl FnOnce() -> Result<DynamicImage, ServeError>,
+) -> Result<Vec<u8>, ServeError> {
+    let mut hasher = DefaultHasher::new();
+    params.hash(&mut hasher);
+    for file in files {
+        file.hash(&mut hasher);
+        if let Ok(metadata) = fs::metadata(file) {
+            metadata.len().hash(&mut hasher);
+            metadata.modified().ok().hash(&mut hasher);
+        }
+    }
+    let cache_file = cache_dir.map(|dir| dir.join(format!("{:016x}.png", hasher.finish())));
+
+    if let Some(file) = &cache_file {
+        if let Ok(bytes) = fs::read(file) {
+            // 以修改时间记录最近使用时间，清理时保留常用的文件
+            let _ = fs::File::options()
+                .write(true)
+                .open(file)
+                .and_then(|f| f.set_modified(SystemTime::now()));
+            return Ok(bytes);
+        }
+    }
+
+    let mut buffer = Cursor::new(Vec::new());
+    render()?
+        .write_to(&mut buffer, image::ImageOutputFormat::Png)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("PNG 编码失败: {}", e)))?;
+    let bytes = buffer.into_inner();
+
+    // 缓存写入失败不影响本次请求
+    if let Some(file) = &cache_file {
+        if let Err(e) = write_atomically(file, &bytes) {
+            eprintln!("写入缩略图缓存失败 {}: {}", file.display(), e);
+        }
+        if TEMP_COUNTER.load(Ordering::Relaxed) % SWEEP_INTERVAL == 1 {
+            if let Some(dir) = file.parent() {
+                sweep(dir, MAX_CACHE_BYTES);
+            }
+        }
+    }
+    Ok(bytes)
+}
+
+// 缓存超出上限时删除最久未使用的文件，直到总大小不超过上限
+fn sweep(dir: &Path, max_bytes: u64) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut files: Vec<(SystemTime, u64, PathBuf)> = entries
+        .flatten()
+        // 只清理缓存的 PNG，正在写入的临时文件不动
+        .filter(|entry| entry.path().extension().is_some_and(|e| e == "png"))
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok().filter(|m| m.is_file())?;
+            Some((metadata.modified().ok()?, metadata.len(), entry.path()))
+        })
+        .collect();
+    let mut total: u64 = files.iter().map(|(_, len, _)| len).sum();
+    if total <= max_bytes {
+        return;
+    }
+
+    files.sort();
+    for (_, len, path) in files {
+        if total <= max_bytes {
+            break;
+        }
+        if fs::remove_file(&path).is_ok() {
+            total -= len;
+        }
+    }
+}
+
+// 先写入同目录下的临时文件再重命名，读取缓存时不会读到并发请求或中途崩溃留下的不完整文件
+fn write_atomically(file: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    if let Some(parent) = file.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let temp = file.with_extension(format!(
+        "{}-{}.tmp",
+        std::process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let renamed = fs::write(&temp, bytes).and_then(|_| fs::rename(&temp, file));
+    if renamed.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    renamed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn sweep_removes_least_recently_used_files() {
+        let dir = std::env::temp_dir().join(format!("thumbnail_sweep_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let now = SystemTime::now();
+        for (index, name) in ["old.png", "middle.png", "new.png"].iter().enumerate() {
+            let path = dir.join(name);
+            fs::write(&path, [0u8; 100]).unwrap();
+            let file = fs::File::options().write(true).open(&path).unwrap();
+            file.set_modified(now - Duration::from_secs(100 * (3 - index as u64))).unwrap();
+        }
+        fs::write(dir.join("pending.tmp"), [0u8; 100]).unwrap();
+
+        sweep(&dir, 250);
+        assert!(!dir.join("old.png").exists());
+        assert!(dir.join("middle.png").exists() && dir.join("new.png").exists());
+        assert!(dir.join("pending.tmp").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,565 @@
+// NIfTI 三维体数据：读取 .nii/.nii.gz，计算体积指标、表面距离以及逐切片结果
+
+use crate::mask::MaskEncoding;
+use crate::render;
+use flate2::read::GzDecoder;
+use image::{DynamicImage, GrayImage};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufReader, Read};
+
+// NIfTI-1 头部固定长度
+const NIFTI1_HEADER_SIZE: usize = 348;
+
+// 是否为 NIfTI 体数据文件
+pub fn is_volume_file(filename: &str) -> bool {
+    let lower = filename.to_lowercase();
+    lower.ends_with(".nii") || lower.ends_with(".nii.gz")
+}
+
+// 体数据，按 x 最快、z 最慢的顺序展开
+#[derive(Debug, Clone)]
+pub struct Volume {
+    pub dims: [usize; 3],
+    // 体素间距（毫米），来自 pixdim[1..4]
+    pub spacing: [f64; 3],
+    pub data: Vec<f32>,
+    pub encoding: MaskEncoding,
+}
+
+impl Volume {
+    fn index(&self, x: usize, y: usize, z: usize) -> usize {
+        (z * self.dims[1] + y) * self.dims[0] + x
+    }
+
+    pub fn foreground(&self) -> Vec<bool> {
+        let threshold = self.encoding.threshold();
+        self.data.iter().map(|&v| v > threshold).collect()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct VolumeInfo {
+    pub dims: [usize; 3],
+    pub spacing: [f64; 3],
+    pub slice_count: usize,
+}
+
+// 单个切片（沿 z 轴）的指标
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SliceScore {
+    pub index: usize,
+    pub iou: f64,
+    pub accuracy: f64,
+    pub dice: f64,
+}
+
+// 整个体积的指标，表面距离在任一方为空时无定义
+#[derive(Debug, Clone)]
+pub struct VolumeMetrics {
+    pub iou: f64,
+    pub accuracy: f64,
+    pub dice: f64,
+    pub hd95: Option<f64>,
+    pub assd: Option<f64>,
+    pub slices: Vec<SliceScore>,
+}
+
+pub fn load_volume(path: &str) -> Result<Volume, String> {
+    let file = File::open(path).map_err(|e| format!("无法打开文件 {}: {}", path, e))?;
+    let mut bytes = Vec::new();
+    if path.to_lowercase().ends_with(".gz") {
+        GzDecoder::new(BufReader::new(file))
+            .read_to_end(&mut bytes)
+            .map_err(|e| format!("无法解压 {}: {}", path, e))?;
+    } else {
+        BufReader::new(file)
+            .read_to_end(&mut bytes)
+            .map_err(|e| format!("无法读取 {}: {}", path, e))?;
+    }
+
+    parse_nifti(&bytes).map_err(|e| format!("无法解析 NIfTI {}: {}", path, e))
+}
+
+fn parse_nifti(bytes: &[u8]) -> Result<Volume, String> {
+    if bytes.len() < NIFTI1_HEADER_SIZE {
+        return Err("文件过短".to_string());
+    }
+
+    // 通过 sizeof_hdr 判断字节序
+    let little_endian = match (
+        i32::from_le_bytes(bytes[0..4].try_into().unwrap()),
+        i32::from_be_bytes(bytes[0..4].try_into().unwrap()),
+    ) {
+        (348, _) => true,
+        (_, 348) => false,
+        (540, _) | (_, 540) => return Err("暂不支持 NIfTI-2 格式".to_string()),
+        _ => return Err("不是有效的 NIfTI-1 文件".to_string()),
+    };
+
+    let reader = HeaderReader { bytes, little_endian };
+    let ndim = reader.i16(40).max(0) as usize;
+    if ndim < 2 {
+        return Err(format!("不支持的维度数: {}", ndim));
+    }
+    let dim = |i: usize| -> usize {
+        if i <= ndim {
+            reader.i16(40 + 2 * i).max(1) as usize
+        } else {
+            1
+        }
+    };
+    let dims = [dim(1), dim(2), dim(3)];
+    if (4..=7).any(|i| dim(i) > 1) {
+        return Err("暂不支持四维及以上的 NIfTI 数据".to_string());
+    }
+
+    let datatype = reader.i16(70);
+    let spacing = [
+        reader.f32(80).abs().max(f32::EPSILON) as f64,
+        reader.f32(84).abs().max(f32::EPSILON) as f64,
+        reader.f32(88).abs().max(f32::EPSILON) as f64,
+    ];
+    let vox_offset = reader.f32(108).max(NIFTI1_HEADER_SIZE as f32) as usize;
+    let scl_slope = reader.f32(112);
+    let scl_inter = reader.f32(116);
+
+    let (size, is_float) = match datatype {
+        2 | 256 => (1, false),
+        4 | 512 => (2, false),
+        8 | 768 => (4, false),
+        1024 | 1280 => (8, false),
+        16 => (4, true),
+        64 => (8, true),
+        other => return Err(format!("不支持的数据类型代码: {}", other)),
+    };
+
+    // 尺寸与偏移都来自文件头，按已检查的乘加计算数据范围
+    let end = dims
+        .iter()
+        .try_fold(size, |total: usize, &d| total.checked_mul(d))
+        .and_then(|len| vox_offset.checked_add(len))
+        .ok_or("体素数据大小溢出")?;
+    let raw = bytes.get(vox_offset..end).ok_or("体素数据不完整")?;
+
+    let mut data: Vec<f32> = raw
+        .chunks_exact(size)
+        .map(|chunk| reader.decode(chunk, datatype))
+        .collect();
+
+    // scl_slope 为 0 表示不缩放
+    if scl_slope != 0.0 && (scl_slope != 1.0 || scl_inter != 0.0) {
+        for value in data.iter_mut() {
+            *value = *value * scl_slope + scl_inter;
+        }
+    }
+
+    Ok(Volume {
+        dims,
+        spacing,
+        data,
+        encoding: if is_float || scl_slope.fract() != 0.0 {
+            MaskEncoding::Probability
+        } else {
+            MaskEncoding::Label
+        },
+    })
+}
+
+struct HeaderReader<'a> {
+    bytes: &'a [u8],
+    little_endian: bool,
+}
+
+impl HeaderReader<'_> {
+    fn take<const N: usize>(&self, offset: usize) -> [u8; N] {
+        let mut buf: [u8; N] = self.bytes[offset..offset + N].try_into().unwrap();
+        if !self.little_endian {
+            buf.reverse();
+        }
+        buf
+    }
+
+    fn i16(&self, offset: usize) -> i16 {
+        i16::from_le_bytes(self.take(offset))
+    }
+
+    fn f32(&self, offset: usize) -> f32 {
+        f32::from_le_bytes(self.take(offset))
+    }
+
+    fn decode(&self, chunk: &[u8], datatype: i16) -> f32 {
+        let mut buf = [0u8; 8];
+        buf[..chunk.len()].copy_from_slice(chunk);
+        if !self.little_endian {
+            buf[..chunk.len()].reverse();
+        }
+        match datatype {
+            2 => buf[0] as f32,
+            256 => buf[0] as i8 as f32,
+            4 => i16::from_le_bytes([buf[0], buf[1]]) as f32,
+            512 => u16::from_le_bytes([buf[0], buf[1]]) as f32,
+            8 => i32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f32,
+            768 => u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f32,
+            16 => f32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]),
+            1024 => i64::from_le_bytes(buf) as f32,
+            1280 => u64::from_le_bytes(buf) as f32,
+            64 => f64::from_le_bytes(buf) as f32,
+            _ => 0.0,
+        }
+    }
+}
+
+pub fn volume_info(path: &str) -> Result<VolumeInfo, String> {
+    let volume = load_volume(path)?;
+    Ok(VolumeInfo {
+        dims: volume.dims,
+        spacing: volume.spacing,
+        slice_count: volume.dims[2],
+    })
+}
+
+// 比较 GT 与预测体积，两者尺寸必须一致
+pub fn compare_volumes(gt_path: &str, pred_path: &str) -> Result<VolumeMetrics, String> {
+    let gt = load_volume(gt_path)?;
+    let pred = load_volume(pred_path)?;
+
+    if gt.dims != pred.dims {
+        return Err(format!(
+            "体积尺寸不一致: GT {:?}, 预测 {:?} | GT: {} | 预测: {}",
+            gt.dims, pred.dims, gt_path, pred_path
+        ));
+    }
+
+    let gt_mask = gt.foreground();
+    let pred_mask = pred.foreground();
+    let slice_len = gt.dims[0] * gt.dims[1];
+
+    let mut slices = Vec::with_capacity(gt.dims[2]);
+    let mut totals = Overlap::default();
+    for z in 0..gt.dims[2] {
+        let range = z * slice_len..(z + 1) * slice_len;
+        let overlap = Overlap::count(&gt_mask[range.clone()], &pred_mask[range]);
+        slices.push(SliceScore {
+            index: z,
+            iou: overlap.iou(),
+            accuracy: overlap.accuracy(),
+            dice: overlap.dice(),
+        });
+        totals.add(&overlap);
+    }
+
+    // 表面距离使用 GT 的体素间距
+    let (hd95, assd) = match surface_distances(&gt_mask, &pred_mask, gt.dims, gt.spacing) {
+        Some((gt_to_pred, pred_to_gt)) => {
+            let mut all: Vec<f64> = gt_to_pred.iter().chain(pred_to_gt.iter()).copied().collect();
+            all.sort_by(|a, b| a.total_cmp(b));
+            let mean = |v: &[f64]| v.iter().sum::<f64>() / v.len() as f64;
+            (
+                Some(percentile(&all, 95.0)),
+                Some((mean(&gt_to_pred) + mean(&pred_to_gt)) / 2.0),
+            )
+        }
+        None => (None, None),
+    };
+
+    Ok(VolumeMetrics {
+        iou: totals.iou(),
+        accuracy: totals.accuracy(),
+        dice: totals.dice(),
+        hd95,
+        assd,
+        slices,
+    })
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Overlap {
+    intersection: u64,
+    gt_foreground: u64,
+    pred_foreground: u64,
+    total: u64,
+}
+
+impl Overlap {
+    fn count(gt: &[bool], pred: &[bool]) -> Overlap {
+        let mut overlap = Overlap {
+            total: gt.len() as u64,
+            ..Default::default()
+        };
+        for (&a, &b) in gt.iter().zip(pred) {
+            overlap.gt_foreground += a as u64;
+            overlap.pred_foreground += b as u64;
+            overlap.intersection += (a && b) as u64;
+        }
+        overlap
+    }
+
+    fn add(&mut self, other: &Overlap) {
+        self.intersection += other.intersection;
+        self.gt_foreground += other.gt_foreground;
+        self.pred_foreground += other.pred_foreground;
+        self.total += other.total;
+    }
+
+    fn union(&self) -> u64 {
+        self.gt_foreground + self.pred_foreground - self.intersection
+    }
+
+    // 与二维指标保持一致：双方都为空时视为完全匹配
+    fn iou(&self) -> f64 {
+        match self.union() {
+            0 => 1.0,
+            union => self.intersection as f64 / union as f64,
+        }
+    }
+
+    fn dice(&self) -> f64 {
+        match self.gt_foreground + self.pred_foreground {
+            0 => 1.0,
+            sum => 2.0 * self.intersection as f64 / sum as f64,
+        }
+    }
+
+    fn accuracy(&self) -> f64 {
+        if self.total == 0 {
+            return 1.0;
+        }
+        let correct = self.total - (self.union() - self.intersection);
+        correct as f64 / self.total as f64
+    }
+}
+
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    if sorted.len() == 1 {
+        return sorted[0];
+    }
+    let rank = p / 100.0 * (sorted.len() - 1) as f64;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+}
+
+// 表面体素：前景且 6 邻域中存在背景（越界视为背景）
+fn surface(mask: &[bool], dims: [usize; 3]) -> Vec<bool> {
+    let [nx, ny, nz] = dims;
+    let at = |x: usize, y: usize, z: usize| mask[(z * ny + y) * nx + x];
+    let mut result = vec![false; mask.len()];
+
+    for z in 0..nz {
+        for y in 0..ny {
+            for x in 0..nx {
+                if !at(x, y, z) {
+                    continue;
+                }
+                let border = x == 0 || y == 0 || z == 0 || x + 1 == nx || y + 1 == ny || z + 1 == nz;
+                result[(z * ny + y) * nx + x] = border
+                    || !at(x - 1, y, z)
+                    || !at(x + 1, y, z)
+                    || !at(x, y - 1, z)
+                    || !at(x, y + 1, z)
+                    || !at(x, y, z - 1)
+                    || !at(x, y, z + 1);
+            }
+        }
+    }
+
+    result
+}
+
+// 双向表面距离（毫米）；任一方没有前景时返回 None
+fn surface_distances(
+    gt: &[bool],
+    pred: &[bool],
+    dims: [usize; 3],
+    spacing: [f64; 3],
+) -> Option<(Vec<f64>, Vec<f64>)> {
+    let gt_surface = surface(gt, dims);
+    let pred_surface = surface(pred, dims);
+    if !gt_surface.iter().any(|&v| v) || !pred_surface.iter().any(|&v| v) {
+        return None;
+    }
+
+    let to_pred = distance_transform(&pred_surface, dims, spacing);
+    let to_gt = distance_transform(&gt_surface, dims, spacing);
+
+    let collect = |surface: &[bool], distances: &[f64]| -> Vec<f64> {
+        surface
+            .iter()
+            .zip(distances)
+            .filter(|(&s, _)| s)
+            .map(|(_, &d)| d.sqrt())
+            .collect()
+    };
+
+    Some((collect(&gt_surface, &to_pred), collect(&pred_surface, &to_gt)))
+}
+
+// 各向异性的欧氏距离变换（Felzenszwalb 可分离算法），返回到最近目标体素的平方距离
+fn distance_transform(targets: &[bool], dims: [usize; 3], spacing: [f64; 3]) -> Vec<f64> {
+    let mut field: Vec<f64> = targets
+        .iter()
+        .map(|&t| if t { 0.0 } else { f64::INFINITY })
+        .collect();
+    let strides = [1, dims[0], dims[0] * dims[1]];
+
+    for axis in 0..3 {
+        let len = dims[axis];
+        let mut line = vec![0.0; len];
+        let mut output = vec![0.0; len];
+
+        // 遍历所有沿该轴的直线
+        let stride = strides[axis];
+        for outer in 0..field.len() / (stride * len) {
+            for inner in 0..stride {
+                let start = outer * stride * len + inner;
+                for (i, value) in line.iter_mut().enumerate() {
+                    *value = field[start + i * stride];
+                }
+                distance_transform_1d(&line, spacing[axis], &mut output);
+                for (i, value) in output.iter().enumerate() {
+                    field[start + i * stride] = *value;
+                }
+            }
+        }
+    }
+
+    field
+}
+
+fn distance_transform_1d(f: &[f64], spacing: f64, output: &mut [f64]) {
+    let n = f.len();
+    let mut vertices = vec![0usize; n];
+    let mut bounds = vec![0.0f64; n + 1];
+    let mut k: isize = -1;
+
+    let position = |i: usize| i as f64 * spacing;
+    for q in 0..n {
+        if f[q].is_infinite() {
+            continue;
+        }
+        loop {
+            if k < 0 {
+                k = 0;
+                vertices[0] = q;
+                bounds[0] = f64::NEG_INFINITY;
+                bounds[1] = f64::INFINITY;
+                break;
+            }
+            let v = vertices[k as usize];
+            let s = ((f[q] + position(q).powi(2)) - (f[v] + position(v).powi(2)))
+                / (2.0 * (position(q) - position(v)));
+            if s <= bounds[k as usize] {
+                k -= 1;
+                continue;
+            }
+            k += 1;
+            vertices[k as usize] = q;
+            bounds[k as usize] = s;
+            bounds[k as usize + 1] = f64::INFINITY;
+            break;
+        }
+    }
+
+    if k < 0 {
+        output.fill(f64::INFINITY);
+        return;
+    }
+
+    let mut j = 0;
+    for (q, value) in output.iter_mut().enumerate() {
+        while bounds[j + 1] < position(q) {
+            j += 1;
+        }
+        let v = vertices[j];
+        *value = (position(q) - position(v)).powi(2) + f[v];
+    }
+}
+
+// 读取某层切片上一个体素的值与是否为前景，坐标与 render_slice 显示的图片一致（y 轴朝上）
+pub fn probe(path: &str, x: usize, y: usize, index: usize) -> Result<(f32, bool), String> {
+    let volume = load_volume(path)?;
+    let [nx, ny, nz] = volume.dims;
+    if x >= nx || y >= ny || index >= nz {
+        return Err(format!("坐标越界: ({}, {}, {})，体数据尺寸 {}x{}x{}", x, y, index, nx, ny, nz));
+    }
+    let value = volume.data[volume.index(x, ny - 1 - y, index)];
+    Ok((value, value > volume.encoding.threshold()))
+}
+
+// 渲染某个 z 切片为 PNG 数据 URL，按整个体积的取值范围归一化到灰度
+pub fn render_slice(path: &str, index: usize) -> Result<String, String> {
+    let volume = load_volume(path)?;
+    let [nx, ny, nz] = volume.dims;
+    if index >= nz {
+        return Err(format!("切片索引越界: {} (共 {} 层)", index, nz));
+    }
+
+    let (min, max) = volume
+        .data
+        .iter()
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+
+    // 按放射学习惯将 y 轴朝上显示
+    let image = GrayImage::from_fn(nx as u32, ny as u32, |x, y| {
+        let value = volume.data[volume.index(x as usize, ny - 1 - y as usize, index)];
+        let gray = if max > min {
+            ((value - min) / (max - min) * 255.0).round() as u8
+        } else {
+            0
+        };
+        image::Luma([gray])
+    });
+
+    render::png_data_url(&DynamicImage::ImageLuma8(image))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 构造小端 NIfTI-1 头部，数据紧跟在 352 字节偏移之后
+    fn header(dims: [i16; 3], vox_offset: f32) -> Vec<u8> {
+        let mut bytes = vec![0u8; 352];
+        bytes[0..4].copy_from_slice(&348i32.to_le_bytes());
+        bytes[40..42].copy_from_slice(&3i16.to_le_bytes());
+        for (i, d) in dims.iter().enumerate() {
+            bytes[42 + 2 * i..44 + 2 * i].copy_from_slice(&d.to_le_bytes());
+        }
+        bytes[70..72].copy_from_slice(&2i16.to_le_bytes());
+        for offset in [80, 84, 88] {
+            bytes[offset..offset + 4].copy_from_slice(&1.0f32.to_le_bytes());
+        }
+        bytes[108..112].copy_from_slice(&vox_offset.to_le_bytes());
+        bytes
+    }
+
+    #[test]
+    fn parses_small_volume() {
+        let mut bytes = header([2, 2, 2], 352.0);
+        bytes.extend([0, 1, 0, 1, 1, 0, 1, 0]);
+        let volume = parse_nifti(&bytes).unwrap();
+        assert_eq!(volume.dims, [2, 2, 2]);
+        assert_eq!(volume.data[volume.index(1, 0, 0)], 1.0);
+        assert_eq!(volume.foreground().iter().filter(|&&v| v).count(), 4);
+    }
+
+    #[test]
+    fn rejects_dims_larger_than_data() {
+        let mut bytes = header([i16::MAX; 3], 352.0);
+        bytes.extend([0; 8]);
+        assert!(parse_nifti(&bytes).is_err());
+    }
+
+    #[test]
+    fn rejects_huge_vox_offset() {
+        let mut bytes = header([2, 2, 2], f32::MAX);
+        bytes.extend([0; 8]);
+        assert!(parse_nifti(&bytes).is_err());
+    }
+
+    #[test]
+    fn rejects_short_header() {
+        assert!(parse_nifti(&[0; 100]).is_err());
+    }
+}
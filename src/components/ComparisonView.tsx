@@ -3,7 +3,8 @@ import { Card, Button, Typography, Space, Row, Col, Statistic, Switch, message,
 import { LeftOutlined, RightOutlined, ReloadOutlined, BarChartOutlined, DownloadOutlined } from '@ant-design/icons';
 import { invoke } from '@tauri-apps/api/core';
 import { showErrorDialog } from '../utils/errorDialog';
-import { ComparisonResult } from '../types';
+import { chooseCollisionPolicy } from '../utils/exportPlan';
+import { ComparisonResult, ExportImageRequest } from '../types';
 import { getIouStatus } from '../utils';
 import ImageComparisonGrid, { getSortedEntries } from './ImageComparisonGrid';
 import SafeImage from './SafeImage';
@@ -45,17 +46,31 @@ const ComparisonView: React.FC<ComparisonViewProps> = ({ results, onReset }) =>
       const selectedResults = results.filter(r => selectedImages.has(r.filename));
       const imageFiles = selectedResults.map(result => ({
         filename: result.filename,
-        model_paths: result.paths // 保持模型名称和路径的对应关系
+        model_paths: result.paths, // 保持模型名称和路径的对应关系
+        iou_scores: result.iou_scores,
+        accuracy_scores: result.accuracy_scores,
+        dice_scores: result.dice_scores
       }));
       
+      const request: ExportImageRequest = {
+        export_folder: exportFolder,
+        image_files: imageFiles
+      };
+
+      // 3. 预览导出计划，有重名时让用户选择跳过、重命名或覆盖
+      message.loading('正在检查导出文件...', 0);
+      const onCollision = await chooseCollisionPolicy(request);
+      message.destroy();
+      if (!onCollision) {
+        message.info('已取消导出');
+        return;
+      }
+
       message.loading('正在导出图片文件...', 0);
       
-      // 3. 调用后端命令导出图片
+      // 4. 调用后端命令导出图片
       const exportResult = await invoke<string>('export_selected_images', {
-        request: {
-          export_folder: exportFolder,
-          image_files: imageFiles
-        }
+        request: { ...request, on_collision: onCollision }
       });
       
       message.destroy();
@@ -272,6 +272,7 @@ const ImageComparisonGrid: React.FC<ImageComparisonGridProps> = ({
               <SafeImage
                 src={path}
                 alt={`${name} - ${result.filename}`}
+                thumbnailSize={defaultImageHeight * 2}
                 style={{ 
                   width: '100%', 
                   height: `${defaultImageHeight}px`,
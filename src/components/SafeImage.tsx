@@ -1,6 +1,7 @@
 import React, { useState, useEffect } from 'react';
 import { Image, Alert } from 'antd';
 import { convertFileSrc } from '@tauri-apps/api/core';
+import { thumbnailUrl } from '../utils/thumbnails';
 
 interface SafeImageProps {
   src: string;
@@ -8,6 +9,8 @@ interface SafeImageProps {
   style?: React.CSSProperties;
   maxWidth?: string;
   maxHeight?: string;
+  // 设置时通过缩略图服务加载该尺寸（长边像素）的缩略图，不读取原图
+  thumbnailSize?: number;
 }
 
 const SafeImage: React.FC<SafeImageProps> = ({ 
@@ -15,7 +18,8 @@ const SafeImage: React.FC<SafeImageProps> = ({
   alt, 
   style, 
   maxWidth = '100%', 
-  maxHeight = '200px' 
+  maxHeight = '200px',
+  thumbnailSize
 }) => {
   const [imageSrc, setImageSrc] = useState<string>('');
   const [error, setError] = useState<string | null>(null);
@@ -27,8 +31,8 @@ const SafeImage: React.FC<SafeImageProps> = ({
         setLoading(true);
         setError(null);
         
-        // 使用 convertFileSrc 转换路径
-        const convertedSrc = convertFileSrc(src);
+        // 缩略图走 thumb 协议，完整图片使用 convertFileSrc 转换路径
+        const convertedSrc = thumbnailSize ? thumbnailUrl(src, thumbnailSize) : convertFileSrc(src);
         setImageSrc(convertedSrc);
         setLoading(false);
       } catch (err) {
@@ -41,7 +45,7 @@ const SafeImage: React.FC<SafeImageProps> = ({
     if (src) {
       loadImage();
     }
-  }, [src]);
+  }, [src, thumbnailSize]);
 
   const handleImageError = (e: any) => {
     console.error('Image load error:', e);
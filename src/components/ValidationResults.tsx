@@ -18,6 +18,9 @@ const ValidationResults: React.FC<ValidationResultsProps> = ({
   onReset,
   loading
 }) => {
+  const extraFiles = result.extra_files ?? {};
+  const problemFolders = new Set([...Object.keys(result.missing_files), ...Object.keys(extraFiles)]);
+
   return (
     <div>
       <Alert
@@ -48,9 +51,9 @@ const ValidationResults: React.FC<ValidationResultsProps> = ({
           <Card>
             <Statistic
               title="存在问题的文件夹"
-              value={Object.keys(result.missing_files).length}
+              value={problemFolders.size}
               prefix={<FolderOutlined />}
-              valueStyle={{ color: Object.keys(result.missing_files).length > 0 ? '#cf1322' : '#52c41a' }}
+              valueStyle={{ color: problemFolders.size > 0 ? '#cf1322' : '#52c41a' }}
             />
           </Card>
         </Col>
@@ -115,6 +118,39 @@ const ValidationResults: React.FC<ValidationResultsProps> = ({
         </Card>
       )}
 
+      {Object.keys(extraFiles).length > 0 && (
+        <Card 
+          title={
+            <Space>
+              <ExclamationCircleOutlined />
+              多余文件详情
+            </Space>
+          }
+          style={{ marginTop: '24px' }}
+        >
+          <Space direction="vertical" style={{ width: '100%' }}>
+            {Object.entries(extraFiles).map(([folder, files]) => (
+              <Card key={folder} size="small" style={{ backgroundColor: '#f6ffed' }}>
+                <Title level={5} style={{ margin: 0, marginBottom: '12px' }}>
+                  <FolderOutlined style={{ marginRight: '8px' }} />
+                  {folder}
+                </Title>
+                <List
+                  dataSource={files}
+                  renderItem={(file) => (
+                    <List.Item style={{ padding: '4px 0' }}>
+                      <Typography.Text type="warning">
+                        • {file}
+                      </Typography.Text>
+                    </List.Item>
+                  )}
+                />
+              </Card>
+            ))}
+          </Space>
+        </Card>
+      )}
+
       <div style={{ 
         marginTop: '32px', 
         textAlign: 'center'
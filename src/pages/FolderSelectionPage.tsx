@@ -1,5 +1,6 @@
 import React from 'react';
 import { useNavigate } from 'react-router-dom';
+import { Modal } from 'antd';
 import { invoke } from '@tauri-apps/api/core';
 import { showErrorDialog } from '../utils/errorDialog';
 import { ValidationResult, FolderData } from '../types';
@@ -26,11 +27,70 @@ const FolderSelectionPage: React.FC = () => {
   const setLoading = useSetLoading();
   const setCurrentHistoryRecordId = useSetCurrentHistoryRecordId();
 
-  const handleFoldersSelected = async (selectedFolders: FolderData) => {
-    setFolders(selectedFolders);
+  // 旧版历史记录中的文件夹没有经过用户选择，后端会拒绝读取：逐个打开以原路径为初始位置的选择对话框重新授权，
+  // 用户选了别的文件夹时替换原路径；取消时返回 null
+  const regrantFolders = async (selectedFolders: FolderData): Promise<FolderData | null> => {
+    const paths = [
+      selectedFolders.original,
+      selectedFolders.gt,
+      selectedFolders.my,
+      ...selectedFolders.comparison.map(f => f.path)
+    ].filter(Boolean);
+    const ungranted = await invoke<string[]>('ungranted_folders', { folders: paths });
+    if (ungranted.length === 0) {
+      return selectedFolders;
+    }
+
+    const confirmed = await new Promise<boolean>((resolve) => {
+      Modal.confirm({
+        title: '需要重新选择文件夹',
+        content: (
+          <div style={{ whiteSpace: 'pre-line', wordBreak: 'break-all' }}>
+            {`出于安全限制，以下文件夹需要在对话框中重新选择一次才能读取：\n${ungranted.join('\n')}`}
+          </div>
+        ),
+        okText: '重新选择',
+        cancelText: '取消',
+        onOk: () => resolve(true),
+        onCancel: () => resolve(false)
+      });
+    });
+    if (!confirmed) {
+      return null;
+    }
+
+    const replaced = new Map<string, string>();
+    for (const path of ungranted) {
+      try {
+        replaced.set(path, await invoke<string>('select_folder', { defaultPath: path }));
+      } catch {
+        return null;
+      }
+    }
+    // 原路径为文件（COCO 标注）时保留原路径，只要所在文件夹已被选择即可读取
+    const swap = (path: string) => {
+      const selected = replaced.get(path);
+      return selected && !/\.json$/i.test(path) ? selected : path;
+    };
+    return {
+      ...selectedFolders,
+      original: swap(selectedFolders.original),
+      gt: swap(selectedFolders.gt),
+      my: swap(selectedFolders.my),
+      comparison: selectedFolders.comparison.map(f => ({ ...f, path: swap(f.path) }))
+    };
+  };
+
+  const handleFoldersSelected = async (initialFolders: FolderData) => {
+    setFolders(initialFolders);
     setLoading(true);
     
     try {
+      const selectedFolders = await regrantFolders(initialFolders);
+      if (!selectedFolders) {
+        return;
+      }
+      setFolders(selectedFolders);
       const comparisonPaths = selectedFolders.comparison.map(f => f.path);
       const allFolders = [selectedFolders.original, selectedFolders.gt, selectedFolders.my, ...comparisonPaths];
       const result = await invoke<ValidationResult>('validate_folders', { folders: allFolders });
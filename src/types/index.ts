@@ -1,15 +1,487 @@
 export interface ValidationResult {
   is_valid: boolean;
   common_files: string[];
-  missing_files: Record<string, string[]>;
+  missing_files: Record<string, string[]>; // 按角色 id：其他文件夹中存在而此处缺失的文件
+  extra_files?: Record<string, string[]>; // 按角色 id：只有此处存在的文件
+  filter_stats?: Record<string, FilterStats>; // 每个文件夹被各个过滤条件去除的文件数量
+  split_removed?: number; // 被数据划分文件去除的文件数量
+}
+
+// 深度预检（preflight_check）中单个文件的检查结果
+export interface FileCheck {
+  file: string;
+  dims: number[]; // 图片为 [宽, 高]，体数据为 [x, y, z]
+  channels?: number | null;
+  unique_values?: number | null; // 仅完整解码时统计
+}
+
+export interface FileIssue {
+  file: string;
+  message: string;
+}
+
+export interface PreflightFolderReport {
+  role: string;
+  checked: number;
+  unreadable: FileIssue[];
+  size_mismatches: FileIssue[];
+  channel_counts: Record<string, number>; // 通道数 -> 文件数
+  non_binary: FileIssue[];
+  files: FileCheck[];
+  readiness: 'ready' | 'warning' | 'blocked';
+  reasons: string[];
+}
+
+export interface FilterStats {
+  hidden: number;
+  not_included: number;
+  excluded: number;
 }
 
 export interface ComparisonResult {
   filename: string;
+  group?: string; // 递归扫描时文件所在的子文件夹，顶层文件为空字符串
   iou_scores: Record<string, number>;
   accuracy_scores: Record<string, number>;
   dice_scores: Record<string, number>;
+  // 以下仅对 NIfTI 体数据计算
+  hd95_scores?: Record<string, number>;
+  assd_scores?: Record<string, number>;
+  slice_scores?: Record<string, SliceScore[]>;
+  // 仅在提供调色板时按类别计算
+  class_scores?: Record<string, ClassScore[]>;
+  // 仅在有多个 GT 来源时计算，reference_scores 以方法 id 为键
+  annotator_agreement?: AnnotatorAgreement | null;
+  reference_scores?: Record<string, MethodReferenceScores>;
+  paths: Record<string, string>;
+}
+
+// 多标注者一致性
+export interface PairAgreement {
+  a: string;
+  b: string;
+  dice: number;
+}
+
+export interface RaterPerformance {
+  id: string;
+  sensitivity: number;
+  specificity: number;
+}
+
+export interface AnnotatorAgreement {
+  pairwise: PairAgreement[];
+  mean_dice: number;
+  fleiss_kappa: number;
+  raters: RaterPerformance[]; // STAPLE 估计的每个标注者表现
+}
+
+export interface ReferenceScore {
+  reference: string; // 标注者的角色 id，或 'majority_vote'、'staple'
+  iou: number;
+  dice: number;
+  accuracy: number;
+}
+
+export interface MethodReferenceScores {
+  scores: ReferenceScore[];
+  mean_annotator_dice: number;
+  within_human_variability: boolean; // 与标注者的平均 Dice 不低于标注者之间的平均 Dice
+}
+
+// 无参考模式（calculate_agreement）：方法两两之间的一致性，矩阵按 methods 的顺序排列
+export interface AgreementMatrix {
+  iou: (number | null)[][]; // 无法读取的配对为 null
+  dice: (number | null)[][];
+}
+
+export interface ImageAgreement {
+  filename: string;
+  group: string;
+  matrix: AgreementMatrix;
+  disagreement: number | null; // 1 - 可读取配对的 Dice 平均值，没有可比较的配对时为 null
   paths: Record<string, string>;
+  errors: string[];
+}
+
+export interface AgreementReport {
+  methods: string[];
+  images: ImageAgreement[]; // 按分歧从大到小排序
+  overall: AgreementMatrix; // 每对方法只在可读取该配对的图片上平均
+  pair_counts: number[][]; // 每对方法参与平均的图片数
+  skipped: ImageAgreement[]; // 没有任何可比较配对的图片
+}
+
+// 误差叠加图（get_error_overlay，以及导出时的 error_overlay 选项）
+export interface OverlayOptions {
+  tp_color?: [number, number, number];
+  fp_color?: [number, number, number];
+  fn_color?: [number, number, number];
+  opacity?: number; // 叠加在原始图片上时颜色的不透明度，0 到 1
+}
+
+export interface ErrorOverlayRequest {
+  filename: string;
+  gt_path: string;
+  pred_path: string;
+  original_path?: string; // 提供时叠加在原始图片上
+  palette?: Palette;
+  options?: OverlayOptions;
+}
+
+export interface ExportOverlayOptions {
+  gt_key?: string; // model_paths 中 GT 的键，默认取自请求的 roles（第一个 GT 的 id）
+  original_key?: string; // 原始图片的键，默认取自 roles，为空字符串时不叠加原图
+  palette?: Palette;
+  options?: OverlayOptions;
+}
+
+// 轮廓叠加图（get_contour_overlay，以及导出时的 contour_overlay 选项），线宽以掩码像素计
+export interface ContourStyle {
+  color?: [number, number, number]; // 默认按模型的先后依次取不同颜色
+  width?: number;
+  visible?: boolean; // 默认 true
+}
+
+export interface ContourOptions {
+  gt_color?: [number, number, number];
+  gt_width?: number;
+  show_gt?: boolean;
+  pred_width?: number; // 未单独设置线宽的模型使用的线宽
+  models?: Record<string, ContourStyle>;
+}
+
+export interface ContourPrediction {
+  model: string;
+  path: string;
+}
+
+export interface ContourOverlayRequest {
+  filename: string;
+  gt_path: string;
+  predictions: ContourPrediction[]; // 按显示顺序，决定默认颜色
+  original_path?: string; // 提供时画在原始图片上
+  palette?: Palette;
+  options?: ContourOptions;
+}
+
+export interface ExportContourOptions {
+  gt_key?: string; // 默认取自请求的 roles
+  original_key?: string;
+  palette?: Palette;
+  options?: ContourOptions;
+}
+
+// 导出（export_selected_images，preview_export 只预览不写入）
+export type ExportLayout = 'per_image' | 'per_model' | 'flat';
+export type CollisionPolicy = 'fail' | 'skip' | 'rename' | 'overwrite';
+
+export interface ExportImageInfo {
+  filename: string;
+  model_paths: Record<string, string>;
+  // 命名模板中的 {iou} {accuracy} {dice}
+  iou_scores?: Record<string, number>;
+  accuracy_scores?: Record<string, number>;
+  dice_scores?: Record<string, number>;
+}
+
+export interface ExportImageRequest {
+  export_folder: string;
+  image_files: ExportImageInfo[];
+  error_overlay?: ExportOverlayOptions;
+  contour_overlay?: ExportContourOptions; // 每张图片一张包含全部模型的轮廓叠加图
+  layout?: ExportLayout; // 默认 per_image
+  // 占位符 {model} {stem} {ext}（带点）{group} {iou} {accuracy} {dice}
+  naming_template?: string; // 默认 '{model}_{stem}{ext}'
+  overlay_template?: string; // 默认 '{model}_overlay_{stem}.png'
+  contour_template?: string; // 默认 '{stem}_contours.png'，{model} 为 contours
+  on_collision?: CollisionPolicy; // 默认 fail：有重名时不写入任何文件
+  archive?: ArchiveOptions; // 提供时在导出文件夹中生成一个压缩文件
+  roles?: RoleSet; // 叠加图未给出 gt_key、original_key 时从中取得
+}
+
+export interface ArchiveOptions {
+  format?: 'zip' | 'tar_gz'; // 默认 zip
+  name?: string; // 不含扩展名，默认 export_年月日_时分秒
+  sidecar?: 'csv' | 'json' | 'both'; // 指标文件 metrics.csv / metrics.json，默认 csv
+}
+
+export interface ExportOperation {
+  kind: 'copy' | 'overlay' | 'contour';
+  filename: string;
+  model: string;
+  source: string;
+  target: string;
+  overwrite: boolean;
+  gt_path?: string;
+  original_path?: string;
+  predictions?: ContourPrediction[];
+}
+
+export interface ExportCollision {
+  target: string;
+  sources: string[];
+  exists: boolean;
+  resolved: boolean;
+}
+
+export interface ExportPlan {
+  export_folder: string;
+  archive?: string | null; // 压缩文件路径
+  operations: ExportOperation[];
+  collisions: ExportCollision[];
+  errors: string[];
+  sidecar_name?: string | null; // 压缩包中指标文件的名称（不含扩展名），与导出的文件重名时改为 metrics_1 等
+}
+
+// 逐图指标表（export_metrics_tables）：写入 {name}_long.csv、{name}_wide.csv 与 {name}.xlsx
+export interface MetricsExportRequest {
+  results: ComparisonResult[];
+  export_folder: string;
+  name?: string; // 默认 metrics
+  models?: string[]; // 模型的排列顺序
+  configuration?: Record<string, unknown>; // 写入 xlsx 的运行配置工作表
+  on_collision?: CollisionPolicy;
+}
+
+// 论文用的结果汇总表（generate_results_table）
+export type TableFormat = 'latex' | 'markdown';
+export type TableSpread = 'std' | 'ci95' | 'none';
+
+export interface TableOptions {
+  format?: TableFormat; // 默认 latex（booktabs）
+  metrics?: string[]; // 列的顺序，默认为有值的全部指标
+  models?: string[]; // 行的顺序
+  spread?: TableSpread; // 均值后的 ± 部分，默认标准差
+  decimals?: number; // 默认 3
+  percent?: boolean; // IOU、准确率与 Dice 以百分数显示
+  highlight?: string[]; // 突出显示的模型，默认为 roles 中标记为我的方法的方法
+  roles?: RoleSet;
+  caption?: string;
+  label?: string;
+}
+
+// 自包含的 HTML 报告（export_report）
+export interface ReportRequest {
+  results: ComparisonResult[];
+  export_folder: string;
+  name?: string; // 默认 report
+  on_collision?: CollisionPolicy;
+  title?: string;
+  models?: string[]; // 模型的排列顺序
+  configuration?: Record<string, unknown>; // 运行配置
+  cases?: number; // 每个模型展示的最好与最差案例数，默认 3
+  rank_metric?: string; // 挑选案例所依据的指标，默认 iou
+  thumbnail_size?: number; // 默认 256
+  gt_key?: string; // 默认取自 roles（第一个 GT 的 id）
+  original_key?: string; // 默认取自 roles，为空字符串时不显示原图
+  palette?: Palette; // 彩色掩膜的类别调色板
+  overlay?: OverlayOptions;
+  roles?: RoleSet; // 标记为我的方法的方法在汇总表与案例中突出显示
+}
+
+// 论文用的定性对比图（export_figure），同时写出 PNG 与 SVG
+export type FigureCellMode = 'plain' | 'overlay' | 'contour';
+
+export interface FigureInset {
+  // 放大区域，为相对图片宽高的比例（0 到 1）
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+  size?: number; // 放大图宽度占格子宽度的比例，默认 0.4
+  corner?: 'top_left' | 'top_right' | 'bottom_left' | 'bottom_right';
+  color?: [number, number, number];
+  line_width?: number;
+}
+
+export interface FigureRequest {
+  results: ComparisonResult[]; // 每行一张图片
+  columns: string[]; // 列的顺序，为 paths 中的键
+  export_folder: string;
+  name?: string; // 默认 figure
+  on_collision?: CollisionPolicy;
+  headers?: boolean; // 默认显示列标题
+  labels?: Record<string, string>; // 列标题改名
+  roles?: RoleSet; // 标记为我的方法的列默认标题后加 (Ours)
+  caption_metric?: string; // 在预测格下方标注的指标
+  decimals?: number;
+  mode?: FigureCellMode;
+  inset?: FigureInset;
+  cell_size?: number; // 每格宽度，默认 512，16 到 4096
+  spacing?: number;
+  font_size?: number;
+  gt_key?: string; // 默认取自 roles / role_set（第一个 GT 的 id）
+  original_key?: string; // 默认取自 roles / role_set，为空字符串时没有原图
+  palette?: Palette;
+  overlay?: OverlayOptions;
+  contour?: ContourOptions;
+}
+
+// 闪烁对比动画（export_flicker）：同一张图片的几个角色轮流显示
+export interface FlickerRequest {
+  result: ComparisonResult;
+  roles: string[]; // paths 中的键，至少两个
+  export_folder: string;
+  name?: string; // 不含扩展名，默认 {图片名}_flicker
+  on_collision?: CollisionPolicy;
+  format?: 'gif' | 'apng'; // 默认 gif
+  frame_duration?: number; // 每帧毫秒数，默认 500
+  mode?: FigureCellMode; // 掩码本身、误差叠加图或轮廓
+  show_labels?: boolean; // 默认在每帧左上角标出角色
+  labels?: Record<string, string>; // 标签改名
+  font_size?: number;
+  max_size?: number; // 长边超过时缩小，0 为不限制
+  role_set?: RoleSet; // 角色配置，用于确定 GT 与原始图片的键
+  gt_key?: string; // 默认取自 roles / role_set（第一个 GT 的 id）
+  original_key?: string; // 默认取自 roles / role_set，为空字符串时没有原图
+  palette?: Palette;
+  overlay?: OverlayOptions;
+  contour?: ContourOptions;
+}
+
+// 两个角色之间的差异图（get_difference_image）
+export interface DifferenceOptions {
+  a_only_color?: [number, number, number];
+  b_only_color?: [number, number, number];
+  both_color?: [number, number, number];
+  opacity?: number;
+}
+
+export interface DifferenceRequest {
+  filename: string;
+  path_a: string;
+  path_b: string;
+  original_path?: string;
+  palette?: Palette;
+  options?: DifferenceOptions;
+}
+
+export interface DifferenceImage {
+  image: string;
+  differing_pixels: number;
+  total_pixels: number;
+}
+
+// 像素探针（probe_pixel）
+export interface ProbeRequest {
+  filename: string;
+  paths: Record<string, string>; // 通常为 ComparisonResult.paths
+  x: number;
+  y: number;
+  reference_size?: [number, number]; // 坐标所在的参考尺寸，各文件按比例换算
+  slice?: number; // 体数据的切片索引
+  palette?: Palette;
+  original_key?: string; // 原始图片的键，默认取自 roles；原始图片不按调色板解码
+  roles?: RoleSet;
+}
+
+export interface PixelProbe {
+  role: string;
+  x: number;
+  y: number;
+  raw: number[]; // 原始通道值
+  value?: number | null;
+  foreground?: boolean | null;
+  class_id?: number | null;
+  class_name?: string | null;
+  error?: string | null;
+}
+
+// 数据集级误差热力图（compute_error_heatmaps）
+export interface HeatmapRequest {
+  roles: RoleSet;
+  common_files: string[];
+  palette?: Palette;
+  match_rule?: MatchRule;
+  scan_options?: ScanOptions;
+  grid_size?: [number, number]; // 统一网格的 [宽, 高]，默认 256x256，每边不超过 4096
+  export_folder?: string; // 提供时保存为 PNG
+}
+
+export interface ModelHeatmap {
+  model: string;
+  files: number;
+  width: number;
+  height: number;
+  max_fp_rate: number;
+  max_fn_rate: number;
+  fp_image: string; // PNG 数据 URL，按 0 到 1 的绝对频率着色
+  fn_image: string;
+}
+
+// 单张图片的模型共识图（get_consensus_map）
+export interface ConsensusMap {
+  models: number;
+  width: number;
+  height: number;
+  image: string;
+}
+
+// 调色板：颜色 -> 类别 ID -> 类别名称
+export interface PaletteEntry {
+  color: [number, number, number];
+  class_id: number;
+  name: string;
+}
+
+export interface Palette {
+  entries: PaletteEntry[];
+}
+
+export interface ClassScore {
+  class_id: number;
+  name: string;
+  iou: number;
+  dice: number;
+}
+
+// 体数据单个切片（沿 z 轴）的指标
+export interface SliceScore {
+  index: number;
+  iou: number;
+  accuracy: number;
+  dice: number;
+}
+
+export interface VolumeInfo {
+  dims: [number, number, number];
+  spacing: [number, number, number];
+  slice_count: number;
+}
+
+// 跨文件夹的文件名匹配规则
+export interface MatchRule {
+  mode: 'exact' | 'stem' | 'regex';
+  strip_prefixes?: string[];
+  strip_suffixes?: string[];
+  pattern?: string; // 正则模式下使用第一个捕获组（或名为 key 的捕获组）作为匹配键
+}
+
+// 文件夹扫描选项
+// 文件夹角色（validate_roles、calculate_role_comparisons、preflight_check）：结果中的路径与指标以角色 id 为键
+export interface RoleSource {
+  id: string;
+  name?: string; // 显示名称，为空时使用 id
+  path: string;
+}
+
+export interface MethodRole extends RoleSource {
+  ours?: boolean; // 是否为我的方法；生成汇总表、报告与对比图时至少需要标记一个
+}
+
+export interface RoleSet {
+  original?: RoleSource | null; // 原始图片可选
+  ground_truths: RoleSource[]; // 第一个 GT 来源作为计算指标的基准
+  methods: MethodRole[];
+}
+
+export interface ScanOptions {
+  recursive?: boolean; // 递归扫描子文件夹，文件以相对路径（/ 分隔）作为标识
+  include_hidden?: boolean; // 默认跳过隐藏文件与 macOS 的 ._* 资源文件
+  include?: string[]; // 包含的 glob 模式，为空时包含全部
+  exclude?: string[]; // 排除的 glob 模式
+  split_file?: string; // 数据划分文件，每行一个文件名，只对比列出的文件；须为对话框或拖放选择过的文件
 }
 
 export interface ComparisonFolder {
@@ -0,0 +1,51 @@
+import { Modal, Radio, Space, Typography } from 'antd';
+import { invoke } from '@tauri-apps/api/core';
+import { CollisionPolicy, ExportImageRequest, ExportPlan } from '../types';
+
+const { Text } = Typography;
+
+const MAX_LISTED = 10;
+
+/**
+ * 导出前预览导出计划：没有重名时直接导出；有重名时列出冲突的文件，让用户选择跳过、重命名或覆盖
+ * @returns 选择的冲突处理方式，用户取消时返回 null
+ */
+export async function chooseCollisionPolicy(request: ExportImageRequest): Promise<CollisionPolicy | null> {
+  const plan = await invoke<ExportPlan>('preview_export', { request: { ...request, on_collision: 'fail' } });
+  if (plan.collisions.length === 0) {
+    return 'fail';
+  }
+
+  let policy: CollisionPolicy = 'rename';
+  const existing = plan.collisions.filter(c => c.exists).length;
+  const listed = plan.collisions.slice(0, MAX_LISTED).map(c => c.target);
+
+  return new Promise((resolve) => {
+    Modal.confirm({
+      title: `${plan.collisions.length} 个导出文件重名`,
+      width: 560,
+      content: (
+        <Space direction="vertical" style={{ width: '100%' }}>
+          <Text type="secondary">
+            {existing > 0 ? `其中 ${existing} 个文件已存在于导出文件夹中。` : '多个文件将导出到同一位置。'}
+          </Text>
+          <div style={{ maxHeight: 160, overflow: 'auto', fontSize: 12, wordBreak: 'break-all' }}>
+            {listed.map(target => <div key={target}>{target}</div>)}
+            {plan.collisions.length > MAX_LISTED && <div>……</div>}
+          </div>
+          <Radio.Group defaultValue={policy} onChange={(e) => { policy = e.target.value; }}>
+            <Space direction="vertical">
+              <Radio value="rename">重命名（添加 _1、_2 等后缀）</Radio>
+              <Radio value="skip">跳过重名的文件</Radio>
+              <Radio value="overwrite">覆盖已有的文件</Radio>
+            </Space>
+          </Radio.Group>
+        </Space>
+      ),
+      okText: '继续导出',
+      cancelText: '取消',
+      onOk: () => resolve(policy),
+      onCancel: () => resolve(null)
+    });
+  });
+}
@@ -0,0 +1,42 @@
+// 缩略图服务（后端自定义协议 thumb）的地址，只能访问验证通过的实验文件夹中的文件
+const THUMB_BASE = navigator.userAgent.includes('Windows')
+  ? 'http://thumb.localhost'
+  : 'thumb://localhost';
+
+const thumbUrl = (route: string, params: Record<string, string | number | undefined>): string => {
+  const query = new URLSearchParams();
+  Object.entries(params).forEach(([key, value]) => {
+    if (value !== undefined) query.set(key, String(value));
+  });
+  return `${THUMB_BASE}/${route}?${query.toString()}`;
+};
+
+export interface PyramidInfo {
+  width: number;
+  height: number;
+  tile: number;
+  levels: number;
+}
+
+export const thumbnailUrl = (path: string, size = 256): string =>
+  thumbUrl('thumbnail', { path, size });
+
+// level 每增加 1 尺寸缩小一半，x、y 为图块的列与行
+export const tileUrl = (path: string, level: number, x: number, y: number, tile = 512): string =>
+  thumbUrl('tile', { path, level, x, y, tile });
+
+export const overlayUrl = (
+  gt: string,
+  pred: string,
+  filename: string,
+  original?: string,
+  size?: number
+): string => thumbUrl('overlay', { gt, pred, filename, original, size });
+
+export const fetchPyramidInfo = async (path: string, tile = 512): Promise<PyramidInfo> => {
+  const response = await fetch(thumbUrl('info', { path, tile }));
+  if (!response.ok) {
+    throw new Error(await response.text());
+  }
+  return response.json();
+};